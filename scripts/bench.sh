@@ -0,0 +1,4 @@
+#!/bin/sh
+
+time bin/trains --input scripts/bench.json
+time bin/trains --input scripts/bench.json --search dijkstra
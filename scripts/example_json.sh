@@ -0,0 +1 @@
+bin/trains --input scripts/example.json
@@ -1,7 +1,119 @@
-use clap::Parser;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{stdin, BufReader, Read};
+
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
 
 #[derive(Parser, Debug)]
-#[clap()]
+#[clap(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    #[clap(flatten)]
+    pub input: Input,
+
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output_format: OutputFormat,
+
+    #[clap(flatten)]
+    pub solver: Solver,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Report every problem in the network without solving it
+    Validate {
+        #[clap(flatten)]
+        input: Input,
+
+        #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+        output_format: OutputFormat,
+    },
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct Solver {
+    #[clap(long, value_enum, default_value_t = Search::AStar)]
+    pub search: Search,
+
+    #[clap(long, value_enum, default_value_t = Objective::Makespan)]
+    pub objective: Objective,
+
+    /// Stop the heuristic search after this many seconds and keep the best itinerary found
+    #[clap(long)]
+    pub time_limit: Option<u64>,
+
+    /// Run the exact search on up to this many threads, no more than the machine has cores
+    #[clap(long)]
+    pub threads: Option<usize>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Search {
+    /// Plain dijkstra's over the state space
+    Dijkstra,
+    /// A* with a lower bound of the remaining time as heuristic
+    #[default]
+    #[clap(name = "astar")]
+    AStar,
+    /// Greedy insertion improved by local search, for networks too large to solve exactly
+    Heuristic,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Objective {
+    /// Time until the last train finishes
+    #[default]
+    Makespan,
+    /// Sum of the time every train is running
+    Total,
+    /// Makespan first, then total train time among plans of equal makespan
+    MakespanThenTotal,
+    /// Sum of every train's activation and running cost
+    Cost,
+    /// Sum of the time every package is delivered, times its priority
+    WeightedCompletion,
+}
+
+#[derive(Args, Debug)]
+pub struct Input {
+    /// Read the network from a JSON file instead of the flags. Use `-` for stdin.
+    #[clap(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = &["station", "route", "package", "train"]
+    )]
+    pub input: Option<String>,
+
+    #[clap(flatten)]
+    pub network: Network,
+}
+
+impl Input {
+    pub fn network(self) -> Result<Network> {
+        match self.input {
+            Some(path) if path == "-" => Network::from_reader(stdin().lock()),
+            Some(path) => Network::from_reader(BufReader::new(
+                File::open(&path)
+                    .map_err(|error| anyhow!("open `{path}` fail with error `{error}`"))?,
+            )),
+            None => Ok(self.network),
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Network {
     #[clap(name = "station", long, value_parser = parser::parse_station)]
     pub stations: Vec<Station>,
@@ -16,16 +128,43 @@ pub struct Network {
     pub trains: Vec<Train>,
 }
 
-#[derive(Debug, Clone)]
+impl Network {
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        serde_json::from_reader(reader)
+            .map_err(|error| anyhow!("parse network json fail with error `{error}`"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Station {
     pub name: String,
+    /// Time to pick or drop any package at this station
+    #[serde(default)]
+    pub handling_time: u32,
+    /// Additional time to pick or drop a package, per unit of its weight
+    #[serde(default)]
+    pub handling_time_per_weight: u32,
+    /// Trains can leave packages here for other trains to carry on
+    #[serde(default)]
+    pub hub: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Route {
     pub name: String,
     pub from_to: (String, String),
     pub travel_time: u32,
+    /// Trains can only travel from the first station to the second one
+    #[serde(default)]
+    pub oneway: bool,
+    /// Maximum number of trains on the route at the same time, e.g. 1 for a single track
+    #[serde(default)]
+    pub capacity: Option<u32>,
+    /// Only trains with every one of these tags may use the route, e.g. `narrow-gauge`
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
 }
 
 impl Route {
@@ -34,67 +173,261 @@ impl Route {
             name,
             from_to: (from, to),
             travel_time,
+            oneway,
+            capacity,
+            tags,
         } = self.clone();
 
         Self {
             name,
             from_to: (to, from),
             travel_time,
+            oneway,
+            capacity,
+            tags,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Package {
     pub name: String,
     pub weight: u32,
     pub from_to: (String, String),
+    /// Earliest time the package can be picked
+    #[serde(default)]
+    pub release: Option<u32>,
+    /// Latest time the package must be dropped at its destination
+    #[serde(default)]
+    pub deadline: Option<u32>,
+    /// Size of the package in other capacity dimensions than weight, e.g. `volume` or `pallets`
+    #[serde(default)]
+    pub sizes: BTreeMap<String, u32>,
+    /// Only trains with every one of these tags may carry the package, e.g. `refrigerated`
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// How much an early delivery matters for `--objective weighted-completion`, 1 by default
+    #[serde(default)]
+    pub priority: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Train {
     pub name: String,
     pub capacity: u32,
     pub initial_station_name: String,
+    /// Operating cost per unit of time the train is running
+    #[serde(default)]
+    pub cost_per_time: u32,
+    /// Fixed cost paid once if the train is used at all
+    #[serde(default)]
+    pub activation_cost: u32,
+    /// Station the train must be back at once it is done, e.g. its depot
+    #[serde(default)]
+    pub end_station_name: Option<String>,
+    /// Capacity of the train in other dimensions than weight, unlimited in dimensions left out
+    #[serde(default)]
+    pub capacities: BTreeMap<String, u32>,
+    /// What the train is fit for, e.g. `refrigerated` or `narrow-gauge`
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// Time the crew starts its shift, before which the train cannot move
+    #[serde(default)]
+    pub ready_time: u32,
+    /// Longest time the train may run from the start of its shift until it is done
+    #[serde(default)]
+    pub max_duty_time: Option<u32>,
+}
+
+impl Display for Station {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "--station {}", self.name)?;
+
+        if self.handling_time > 0 {
+            write!(f, ",handling={}", self.handling_time)?;
+        }
+
+        if self.handling_time_per_weight > 0 {
+            write!(f, ",handling_per_weight={}", self.handling_time_per_weight)?;
+        }
+
+        if self.hub {
+            write!(f, ",hub")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (from, to) = &self.from_to;
+
+        write!(f, "--route {},{from},{to},{}", self.name, self.travel_time)?;
+
+        if self.oneway {
+            f.write_str(",oneway")?;
+        }
+
+        if let Some(capacity) = self.capacity {
+            write!(f, ",capacity={capacity}")?;
+        }
+
+        write_tags(f, &self.tags)
+    }
+}
+
+impl Display for Package {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (from, to) = &self.from_to;
+
+        write!(f, "--package {},{},{from},{to}", self.name, self.weight)?;
+
+        if let Some(release) = self.release {
+            write!(f, ",release={release}")?;
+        }
+
+        if let Some(deadline) = self.deadline {
+            write!(f, ",deadline={deadline}")?;
+        }
+
+        for (dimension, size) in &self.sizes {
+            write!(f, ",size.{dimension}={size}")?;
+        }
+
+        if let Some(priority) = self.priority {
+            write!(f, ",priority={priority}")?;
+        }
+
+        write_tags(f, &self.tags)
+    }
+}
+
+impl Display for Train {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "--train {},{},{}",
+            self.name, self.capacity, self.initial_station_name
+        )?;
+
+        if self.cost_per_time > 0 {
+            write!(f, ",cost={}", self.cost_per_time)?;
+        }
+
+        if self.activation_cost > 0 {
+            write!(f, ",activation={}", self.activation_cost)?;
+        }
+
+        if let Some(end_station_name) = &self.end_station_name {
+            write!(f, ",end={end_station_name}")?;
+        }
+
+        for (dimension, capacity) in &self.capacities {
+            write!(f, ",capacity.{dimension}={capacity}")?;
+        }
+
+        if self.ready_time > 0 {
+            write!(f, ",ready={}", self.ready_time)?;
+        }
+
+        if let Some(max_duty_time) = self.max_duty_time {
+            write!(f, ",max_duty={max_duty_time}")?;
+        }
+
+        write_tags(f, &self.tags)
+    }
+}
+
+fn write_tags(f: &mut std::fmt::Formatter<'_>, tags: &BTreeSet<String>) -> std::fmt::Result {
+    for tag in tags {
+        write!(f, ",tag={tag}")?;
+    }
+
+    Ok(())
 }
 
 pub mod parser {
     use anyhow::{anyhow, bail, Result};
     use itertools::Itertools;
 
+    use std::collections::{BTreeMap, BTreeSet};
+
     use crate::args::{Package, Route, Station, Train};
 
     pub fn parse_station(input: &str) -> Result<Station> {
-        if let [name] = input.split(',').collect_vec()[..] {
-            Ok(Station {
+        if let [name, ref options @ ..] = input.split(',').collect_vec()[..] {
+            let mut station = Station {
                 name: name.to_string(),
-            })
+                handling_time: 0,
+                handling_time_per_weight: 0,
+                hub: false,
+            };
+
+            for option in options {
+                match option.split_once('=') {
+                    None if *option == "hub" => station.hub = true,
+                    Some(("handling", time)) => station.handling_time = parse_time(time)?,
+                    Some(("handling_per_weight", time)) => {
+                        station.handling_time_per_weight = parse_time(time)?
+                    }
+                    _ => bail!(
+                        "unknown station option `{option}`, expect `handling=[TIME]`, `handling_per_weight=[TIME]` or `hub`"
+                    ),
+                }
+            }
+
+            Ok(station)
         } else {
-            bail!("[NAME]")
+            bail!("[NAME][,handling=[TIME]][,handling_per_weight=[TIME]][,hub]")
         }
     }
 
     pub fn parse_route(input: &str) -> Result<Route> {
-        if let [name, station1_name, station2_name, travel_time] =
+        if let [name, station1_name, station2_name, travel_time, ref options @ ..] =
             input.split(',').collect_vec()[..]
         {
-            Ok(Route {
+            let mut route = Route {
                 name: name.to_string(),
                 from_to: (station1_name.to_string(), station2_name.to_string()),
                 travel_time: travel_time.parse().map_err(|error| {
                     anyhow!("parse travel_time `{travel_time}` fail with error `{error}`")
                 })?,
-            })
+                oneway: false,
+                capacity: None,
+                tags: BTreeSet::new(),
+            };
+
+            for option in options {
+                match option.split_once('=') {
+                    None if *option == "oneway" => route.oneway = true,
+                    Some(("capacity", capacity)) => {
+                        route.capacity = Some(capacity.parse().map_err(|error| {
+                            anyhow!("parse capacity `{capacity}` fail with error `{error}`")
+                        })?)
+                    }
+                    Some(("tag", tag)) => {
+                        route.tags.insert(parse_tag(tag)?);
+                    }
+                    _ => bail!(
+                        "unknown route option `{option}`, expect `oneway`, `capacity=[CAPACITY]` or `tag=[TAG]`"
+                    ),
+                }
+            }
+
+            Ok(route)
         } else {
-            bail!("[NAME],[STATION1],[STATION2],[TRAVEL_TIME]")
+            bail!("[NAME],[STATION1],[STATION2],[TRAVEL_TIME][,oneway][,capacity=[CAPACITY]][,tag=[TAG]]...")
         }
     }
 
     pub fn parse_package(input: &str) -> Result<Package> {
-        if let [name, weight, start_station_name, destination_station_name] =
+        if let [name, weight, start_station_name, destination_station_name, ref options @ ..] =
             input.split(',').collect_vec()[..]
         {
-            Ok(Package {
+            let mut package = Package {
                 name: name.to_string(),
                 weight: weight.parse().map_err(|error| {
                     anyhow!("parse weight `{weight}` fail with error `{error}`")
@@ -103,24 +436,120 @@ pub mod parser {
                     start_station_name.to_string(),
                     destination_station_name.to_string(),
                 ),
-            })
+                release: None,
+                deadline: None,
+                sizes: BTreeMap::new(),
+                tags: BTreeSet::new(),
+                priority: None,
+            };
+
+            for option in options {
+                match option.split_once('=') {
+                    Some(("release", time)) => package.release = Some(parse_time(time)?),
+                    Some(("deadline", time)) => package.deadline = Some(parse_time(time)?),
+                    Some((key, size)) if key.starts_with("size.") => {
+                        let (dimension, size) = parse_dimension(key, size)?;
+                        package.sizes.insert(dimension, size);
+                    }
+                    Some(("tag", tag)) => {
+                        package.tags.insert(parse_tag(tag)?);
+                    }
+                    Some(("priority", priority)) => {
+                        package.priority = Some(priority.parse().map_err(|error| {
+                            anyhow!("parse priority `{priority}` fail with error `{error}`")
+                        })?)
+                    }
+                    _ => bail!(
+                        "unknown package option `{option}`, expect `release=[TIME]`, `deadline=[TIME]`, `size.[DIMENSION]=[SIZE]`, `tag=[TAG]` or `priority=[PRIORITY]`"
+                    ),
+                }
+            }
+
+            Ok(package)
         } else {
-            bail!("[NAME],[WEIGHT],[START],[DESTINATION]")
+            bail!("[NAME],[WEIGHT],[START],[DESTINATION][,release=[TIME]][,deadline=[TIME]][,size.[DIMENSION]=[SIZE]]...[,tag=[TAG]]...[,priority=[PRIORITY]]")
         }
     }
 
+    fn parse_time(time: &str) -> Result<u32> {
+        time.parse()
+            .map_err(|error| anyhow!("parse time `{time}` fail with error `{error}`"))
+    }
+
     pub fn parse_train(input: &str) -> Result<Train> {
-        if let [name, capacity, initial_station_name] = input.split(',').collect_vec()[..] {
-            Ok(Train {
+        if let [name, capacity, initial_station_name, ref options @ ..] =
+            input.split(',').collect_vec()[..]
+        {
+            let mut train = Train {
                 name: name.to_string(),
                 capacity: capacity.parse().map_err(|error| {
                     anyhow!("parse capacity `{capacity}` fail with error `{error}`")
                 })?,
                 initial_station_name: initial_station_name.to_string(),
-            })
+                cost_per_time: 0,
+                activation_cost: 0,
+                end_station_name: None,
+                capacities: BTreeMap::new(),
+                tags: BTreeSet::new(),
+                ready_time: 0,
+                max_duty_time: None,
+            };
+
+            for option in options {
+                match option.split_once('=') {
+                    Some(("cost", cost)) => train.cost_per_time = parse_cost(cost)?,
+                    Some(("activation", cost)) => train.activation_cost = parse_cost(cost)?,
+                    Some(("end", station_name)) => {
+                        train.end_station_name = Some(station_name.to_string())
+                    }
+                    Some((key, capacity)) if key.starts_with("capacity.") => {
+                        let (dimension, capacity) = parse_dimension(key, capacity)?;
+                        train.capacities.insert(dimension, capacity);
+                    }
+                    Some(("tag", tag)) => {
+                        train.tags.insert(parse_tag(tag)?);
+                    }
+                    Some(("ready", time)) => train.ready_time = parse_time(time)?,
+                    Some(("max_duty", time)) => train.max_duty_time = Some(parse_time(time)?),
+                    _ => bail!(
+                        "unknown train option `{option}`, expect `cost=[COST]`, `activation=[COST]`, `end=[STATION_NAME]`, `capacity.[DIMENSION]=[CAPACITY]`, `tag=[TAG]`, `ready=[TIME]` or `max_duty=[TIME]`"
+                    ),
+                }
+            }
+
+            Ok(train)
         } else {
-            bail!("[NAME],[CAPACITY],[INITIAL_STATION_NAME]")
+            bail!("[NAME],[CAPACITY],[INITIAL_STATION_NAME][,cost=[COST]][,activation=[COST]][,end=[STATION_NAME]][,capacity.[DIMENSION]=[CAPACITY]]...[,tag=[TAG]]...[,ready=[TIME]][,max_duty=[TIME]]")
+        }
+    }
+
+    fn parse_cost(cost: &str) -> Result<u32> {
+        cost.parse()
+            .map_err(|error| anyhow!("parse cost `{cost}` fail with error `{error}`"))
+    }
+
+    /// Split `size.volume=3` or `capacity.volume=20` into the dimension name and its amount.
+    /// Weight is already given positionally, so it is not a valid dimension name.
+    fn parse_dimension(key: &str, amount: &str) -> Result<(String, u32)> {
+        let (_, dimension) = key.split_once('.').unwrap();
+
+        if dimension.is_empty() || dimension == "weight" {
+            bail!("invalid dimension `{dimension}` in `{key}`, expect a name other than `weight`");
+        }
+
+        let amount = amount
+            .parse()
+            .map_err(|error| anyhow!("parse {key} `{amount}` fail with error `{error}`"))?;
+
+        Ok((dimension.to_string(), amount))
+    }
+
+    fn parse_tag(tag: &str) -> Result<String> {
+        if tag.is_empty() {
+            bail!("tag must not be empty");
         }
+
+        Ok(tag.to_string())
     }
 }
 
@@ -139,31 +568,44 @@ pub mod case {
     pub fn direct() -> Network {
         Network {
             stations: vec![
-                Station { name: "A".into() },
-                Station { name: "B".into() },
-                Station { name: "C".into() },
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
             ],
             routes: vec![
                 Route {
                     name: "AB".into(),
                     from_to: ("A".into(), "B".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "BC".into(),
                     from_to: ("B".into(), "C".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
             ],
             packages: vec![Package {
                 name: "P".into(),
                 weight: 5,
                 from_to: ("A".into(), "C".into()),
+                ..Default::default()
             }],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 5,
                 initial_station_name: "A".into(),
+                ..Default::default()
             }],
         }
     }
@@ -181,42 +623,60 @@ pub mod case {
     pub fn choice() -> Network {
         Network {
             stations: vec![
-                Station { name: "A".into() },
-                Station { name: "B".into() },
-                Station { name: "C".into() },
-                Station { name: "D".into() },
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "D".into(),
+                    ..Default::default()
+                },
             ],
             routes: vec![
                 Route {
                     name: "AB".into(),
                     from_to: ("A".into(), "B".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "AC".into(),
                     from_to: ("A".into(), "C".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "BD".into(),
                     from_to: ("B".into(), "D".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "CD".into(),
                     from_to: ("C".into(), "D".into()),
                     travel_time: 50,
+                    ..Default::default()
                 },
             ],
             packages: vec![Package {
                 name: "P".into(),
                 weight: 5,
                 from_to: ("A".into(), "D".into()),
+                ..Default::default()
             }],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 5,
                 initial_station_name: "A".into(),
+                ..Default::default()
             }],
         }
     }
@@ -232,24 +692,36 @@ pub mod case {
     pub fn islands() -> Network {
         Network {
             stations: vec![
-                Station { name: "A".into() },
-                Station { name: "B".into() },
-                Station { name: "C".into() },
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
             ],
             routes: vec![Route {
                 name: "AB".into(),
                 from_to: ("A".into(), "B".into()),
                 travel_time: 10,
+                ..Default::default()
             }],
             packages: vec![Package {
                 name: "P".into(),
                 weight: 5,
                 from_to: ("A".into(), "B".into()),
+                ..Default::default()
             }],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 5,
                 initial_station_name: "A".into(),
+                ..Default::default()
             }],
         }
     }
@@ -266,32 +738,51 @@ pub mod case {
     pub fn diverge() -> Network {
         Network {
             stations: vec![
-                Station { name: "A".into() },
-                Station { name: "B".into() },
-                Station { name: "C".into() },
-                Station { name: "D".into() },
-                Station { name: "E".into() },
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "D".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "E".into(),
+                    ..Default::default()
+                },
             ],
             routes: vec![
                 Route {
                     name: "AB".into(),
                     from_to: ("A".into(), "B".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "BC".into(),
                     from_to: ("B".into(), "C".into()),
                     travel_time: 50,
+                    ..Default::default()
                 },
                 Route {
                     name: "CD".into(),
                     from_to: ("C".into(), "D".into()),
                     travel_time: 40,
+                    ..Default::default()
                 },
                 Route {
                     name: "DE".into(),
                     from_to: ("D".into(), "E".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
             ],
             packages: vec![
@@ -299,17 +790,20 @@ pub mod case {
                     name: "P1".into(),
                     weight: 5,
                     from_to: ("B".into(), "A".into()),
+                    ..Default::default()
                 },
                 Package {
                     name: "P2".into(),
                     weight: 5,
                     from_to: ("D".into(), "E".into()),
+                    ..Default::default()
                 },
             ],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 10,
                 initial_station_name: "C".into(),
+                ..Default::default()
             }],
         }
     }
@@ -325,28 +819,41 @@ pub mod case {
     //
     pub fn multiple_packages_small_train() -> Network {
         Network {
-            stations: vec![Station { name: "A".into() }, Station { name: "B".into() }],
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+            ],
             routes: vec![Route {
                 name: "AB".into(),
                 from_to: ("A".into(), "B".into()),
                 travel_time: 10,
+                ..Default::default()
             }],
             packages: vec![
                 Package {
                     name: "P1".into(),
                     weight: 5,
                     from_to: ("A".into(), "B".into()),
+                    ..Default::default()
                 },
                 Package {
                     name: "P2".into(),
                     weight: 5,
                     from_to: ("A".into(), "B".into()),
+                    ..Default::default()
                 },
             ],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 5,
                 initial_station_name: "A".into(),
+                ..Default::default()
             }],
         }
     }
@@ -362,28 +869,41 @@ pub mod case {
     //
     pub fn multiple_packages_big_train() -> Network {
         Network {
-            stations: vec![Station { name: "A".into() }, Station { name: "B".into() }],
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+            ],
             routes: vec![Route {
                 name: "AB".into(),
                 from_to: ("A".into(), "B".into()),
                 travel_time: 10,
+                ..Default::default()
             }],
             packages: vec![
                 Package {
                     name: "P1".into(),
                     weight: 5,
                     from_to: ("A".into(), "B".into()),
+                    ..Default::default()
                 },
                 Package {
                     name: "P2".into(),
                     weight: 5,
                     from_to: ("A".into(), "B".into()),
+                    ..Default::default()
                 },
             ],
             trains: vec![Train {
                 name: "T".into(),
                 capacity: 10,
                 initial_station_name: "A".into(),
+                ..Default::default()
             }],
         }
     }
@@ -404,21 +924,35 @@ pub mod case {
     pub fn multiple_packages_islands() -> Network {
         Network {
             stations: vec![
-                Station { name: "A1".into() },
-                Station { name: "B1".into() },
-                Station { name: "A2".into() },
-                Station { name: "B2".into() },
+                Station {
+                    name: "A1".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B1".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "A2".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B2".into(),
+                    ..Default::default()
+                },
             ],
             routes: vec![
                 Route {
                     name: "AB1".into(),
                     from_to: ("A1".into(), "B1".into()),
                     travel_time: 10,
+                    ..Default::default()
                 },
                 Route {
                     name: "AB2".into(),
                     from_to: ("A2".into(), "B2".into()),
                     travel_time: 20,
+                    ..Default::default()
                 },
             ],
             packages: vec![
@@ -426,11 +960,13 @@ pub mod case {
                     name: "P1".into(),
                     weight: 5,
                     from_to: ("A1".into(), "B1".into()),
+                    ..Default::default()
                 },
                 Package {
                     name: "P2".into(),
                     weight: 5,
                     from_to: ("A2".into(), "B2".into()),
+                    ..Default::default()
                 },
             ],
             trains: vec![
@@ -438,13 +974,935 @@ pub mod case {
                     name: "T1".into(),
                     capacity: 5,
                     initial_station_name: "A1".into(),
+                    ..Default::default()
                 },
                 Train {
                     name: "T2".into(),
                     capacity: 5,
                     initial_station_name: "A2".into(),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    //   10   10
+    // A--->B--->C
+    //
+    // T, 5, A
+    // P1, 5, B -> C
+    // P2, 5, A -> B
+    //
+    // Solution: 20     A(Pick P2)-B(Drop P2, Pick P1)-C
+    //
+    // Both routes are one-way, so once the train leaves A it can never pick P2.
+    //
+    pub fn one_way() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AB".into(),
+                    from_to: ("A".into(), "B".into()),
+                    travel_time: 10,
+                    oneway: true,
+                    ..Default::default()
+                },
+                Route {
+                    name: "BC".into(),
+                    from_to: ("B".into(), "C".into()),
+                    travel_time: 10,
+                    oneway: true,
+                    ..Default::default()
+                },
+            ],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("B".into(), "C".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![Train {
+                name: "T".into(),
+                capacity: 5,
+                initial_station_name: "A".into(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    //   10   10
+    // A----B----C
+    //
+    // T, 5, A
+    // P, 5, A -> C, release at 30
+    //
+    // Solution: 50     A(Wait 30, Pick)-C
+    //
+    pub fn release() -> Network {
+        let mut network = direct();
+
+        network.packages[0].release = Some(30);
+
+        network
+    }
+
+    //   10   50   40   10
+    // A----B----C----D----E
+    //
+    // T, 10, C
+    // P1, 5, B -> A, deadline at 100
+    // P2, 5, D -> E
+    //
+    // Solution: 170    C-B-A-D-E
+    //
+    // Without the deadline, delivering P2 first takes only 160 but drops P1 at 160.
+    //
+    pub fn deadline() -> Network {
+        let mut network = diverge();
+
+        network.packages[0].deadline = Some(100);
+
+        network
+    }
+
+    //   10   10
+    // A----B----C
+    //
+    // A, handling 5
+    // C, handling 2 per weight
+    //
+    // T, 5, A
+    // P, 5, A -> C
+    //
+    // Solution: 35     A(Pick 5)-C(Drop 10)
+    //
+    pub fn handling() -> Network {
+        let mut network = direct();
+
+        network.stations[0].handling_time = 5;
+        network.stations[2].handling_time_per_weight = 2;
+
+        network
+    }
+
+    //   10 (single track)
+    // A-------B
+    //
+    // T1, 5, A
+    // T2, 5, B
+    // P1, 5, A -> B
+    // P2, 5, B -> A
+    //
+    // Solution: 20     T1: A-B + T2: (Wait 10) B-A
+    //
+    pub fn single_track() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![Route {
+                name: "AB".into(),
+                from_to: ("A".into(), "B".into()),
+                travel_time: 10,
+                capacity: Some(1),
+                ..Default::default()
+            }],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("B".into(), "A".into()),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![
+                Train {
+                    name: "T1".into(),
+                    capacity: 5,
+                    initial_station_name: "A".into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T2".into(),
+                    capacity: 5,
+                    initial_station_name: "B".into(),
+                    ..Default::default()
                 },
             ],
         }
     }
+
+    //   10 (single track)
+    // A-------B
+    //  \     /
+    //   6   6
+    //    \ /
+    //     C
+    //
+    // T1, 5, A
+    // T2, 5, B
+    // P1, 5, A -> B
+    // P2, 5, B -> A
+    //
+    // Solution: 12     T1: A-B + T2: B-C-A
+    //
+    pub fn single_track_detour() -> Network {
+        let mut network = single_track();
+
+        network.stations.push(Station {
+            name: "C".into(),
+            ..Default::default()
+        });
+
+        network.routes.extend([
+            Route {
+                name: "AC".into(),
+                from_to: ("A".into(), "C".into()),
+                travel_time: 6,
+                ..Default::default()
+            },
+            Route {
+                name: "BC".into(),
+                from_to: ("B".into(), "C".into()),
+                travel_time: 6,
+                ..Default::default()
+            },
+        ]);
+
+        network
+    }
+
+    // A---10---B
+    //  \
+    //   15
+    //    \
+    //     C
+    //
+    // T1, 5, A
+    // T2, 5, C
+    // P1, 5, A -> B
+    // P2, 5, A -> B
+    //
+    // Makespan: 25     T1: A-B + T2: C-A-B (total 35)
+    // Total: 30        T1: A-B-A-B
+    pub fn helper_train() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AB".into(),
+                    from_to: ("A".into(), "B".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+                Route {
+                    name: "AC".into(),
+                    from_to: ("A".into(), "C".into()),
+                    travel_time: 15,
+                    ..Default::default()
+                },
+            ],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![
+                Train {
+                    name: "T1".into(),
+                    capacity: 5,
+                    initial_station_name: "A".into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T2".into(),
+                    capacity: 5,
+                    initial_station_name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    // A---10---B
+    //  \
+    //   20
+    //    \
+    //     C
+    //
+    // T1, 5, A
+    // T2, 5, C
+    // T3, 5, B
+    // P1, 5, A -> B
+    // P2, 5, C -> A
+    //
+    // Makespan: 20     T2: C-A, P1 by either T1: A-B (total 30) or T3: B-A-B (total 40)
+    // Total: 30        T2: C-A-B, or T1: A-B + T2: C-A
+    // Makespan then total: 20 (total 30)   T1: A-B + T2: C-A
+    pub fn spare_train() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AB".into(),
+                    from_to: ("A".into(), "B".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+                Route {
+                    name: "AC".into(),
+                    from_to: ("A".into(), "C".into()),
+                    travel_time: 20,
+                    ..Default::default()
+                },
+            ],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("C".into(), "A".into()),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![
+                Train {
+                    name: "T1".into(),
+                    capacity: 5,
+                    initial_station_name: "A".into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T2".into(),
+                    capacity: 5,
+                    initial_station_name: "C".into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T3".into(),
+                    capacity: 5,
+                    initial_station_name: "B".into(),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    // A---10---B
+    //
+    // T1, 5, A, cost 10
+    // T2, 5, B, cost 1
+    // T3, 5, B, activation 25
+    // P, 5, A -> B
+    //
+    // Makespan: 10 (cost 100)  T1: A-B
+    // Cost: 20 (time 20)       T2: B-A-B
+    pub fn costly_trains() -> Network {
+        let mut network = direct();
+
+        network.stations.pop();
+        network.routes.pop();
+        network.packages[0].from_to.1 = "B".into();
+        network.trains = vec![
+            Train {
+                name: "T1".into(),
+                capacity: 5,
+                initial_station_name: "A".into(),
+                cost_per_time: 10,
+                ..Default::default()
+            },
+            Train {
+                name: "T2".into(),
+                capacity: 5,
+                initial_station_name: "B".into(),
+                cost_per_time: 1,
+                ..Default::default()
+            },
+            Train {
+                name: "T3".into(),
+                capacity: 5,
+                initial_station_name: "B".into(),
+                activation_cost: 25,
+                ..Default::default()
+            },
+        ];
+
+        network
+    }
+
+    //   10   10
+    // A----B----C
+    //
+    // T, 5, A, end at A
+    // P, 5, A -> C
+    //
+    // Solution: 40     A-A(Pick)-C-A
+    pub fn depot() -> Network {
+        let mut network = direct();
+
+        network.trains[0].end_station_name = Some("A".into());
+
+        network
+    }
+
+    //   10   10
+    // A----H----B
+    //      |
+    //      | 10
+    //      C
+    //
+    // H is a hub
+    // T1, 10, A
+    // T2, 10, H
+    // P1, 5, A -> B
+    // P2, 5, A -> C
+    //
+    // Solution: 20     T1: A-A(Pick P1, P2)-H(Transfer P2)-B
+    //                  T2: H-H(Pick P2)-C
+    pub fn hub() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "H".into(),
+                    hub: true,
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AH".into(),
+                    from_to: ("A".into(), "H".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+                Route {
+                    name: "HB".into(),
+                    from_to: ("H".into(), "B".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+                Route {
+                    name: "HC".into(),
+                    from_to: ("H".into(), "C".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+            ],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "C".into()),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![
+                Train {
+                    name: "T1".into(),
+                    capacity: 10,
+                    initial_station_name: "A".into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T2".into(),
+                    capacity: 10,
+                    initial_station_name: "H".into(),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+
+    //   10
+    // A----B
+    //
+    // T, 10, A, volume 4
+    // P1, 5, A -> B, volume 3
+    // P2, 5, A -> B, volume 3
+    //
+    // Solution: 30     A-A(Pick P1)-B(Drop P1)-A(Pick P2)-B
+    //
+    pub fn volume() -> Network {
+        let mut network = multiple_packages_big_train();
+
+        for package in &mut network.packages {
+            package.sizes.insert("volume".into(), 3);
+        }
+        network.trains[0].capacities.insert("volume".into(), 4);
+
+        network
+    }
+
+    //   10   10
+    // A----B----C
+    //  \_______/
+    //      10 (narrow-gauge)
+    //
+    // T1, 10, A, narrow-gauge
+    // T2, 10, A, refrigerated
+    // P, 5, A -> C, refrigerated
+    //
+    // Solution: 20     T2: A-A(Pick)-B-C
+    //
+    pub fn tags() -> Network {
+        let mut network = direct();
+
+        network.routes.push(Route {
+            name: "AC".into(),
+            from_to: ("A".into(), "C".into()),
+            travel_time: 10,
+            tags: ["narrow-gauge".into()].into(),
+            ..Default::default()
+        });
+        network.packages[0].tags.insert("refrigerated".into());
+        network.trains = vec![
+            Train {
+                name: "T1".into(),
+                capacity: 10,
+                initial_station_name: "A".into(),
+                tags: ["narrow-gauge".into()].into(),
+                ..Default::default()
+            },
+            Train {
+                name: "T2".into(),
+                capacity: 10,
+                initial_station_name: "A".into(),
+                tags: ["refrigerated".into()].into(),
+                ..Default::default()
+            },
+        ];
+
+        network
+    }
+
+    //   10   10
+    // B----A----C
+    //
+    // T, 10, A
+    // P1, 5, A -> B
+    // P2, 5, A -> C, priority 5
+    //
+    // Makespan: 30
+    // Weighted completion: 80 (P1 at 30, P2 at 10)     A-A(Pick P1, P2)-C-A-B
+    //
+    pub fn priority() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "B".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AB".into(),
+                    from_to: ("A".into(), "B".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+                Route {
+                    name: "AC".into(),
+                    from_to: ("A".into(), "C".into()),
+                    travel_time: 10,
+                    ..Default::default()
+                },
+            ],
+            packages: vec![
+                Package {
+                    name: "P1".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "B".into()),
+                    ..Default::default()
+                },
+                Package {
+                    name: "P2".into(),
+                    weight: 5,
+                    from_to: ("A".into(), "C".into()),
+                    priority: Some(5),
+                    ..Default::default()
+                },
+            ],
+            trains: vec![Train {
+                name: "T".into(),
+                capacity: 10,
+                initial_station_name: "A".into(),
+                ..Default::default()
+            }],
+        }
+    }
+
+    //   10   10
+    // A----B----C
+    //
+    // T1, 5, A, ready 0, max duty 15
+    // T2, 5, A, ready 30
+    // P, 5, A -> C
+    //
+    // Solution: 50     T2: A-A(Pick)-C
+    //
+    pub fn shift() -> Network {
+        let mut network = direct();
+
+        network.trains = vec![
+            Train {
+                name: "T1".into(),
+                capacity: 5,
+                initial_station_name: "A".into(),
+                max_duty_time: Some(15),
+                ..Default::default()
+            },
+            Train {
+                name: "T2".into(),
+                capacity: 5,
+                initial_station_name: "A".into(),
+                ready_time: 30,
+                ..Default::default()
+            },
+        ];
+
+        network
+    }
+
+    //   10 (x)   10 (y)
+    // A--------H--------C
+    //
+    // H is a hub
+    // T1, 10, A, x
+    // T2, 10, H, y
+    // P, 5, A -> C
+    //
+    // Solution: 20     T1: A-A(Pick)-H(Transfer)
+    //                  T2: H-H(Pick)-C
+    //
+    pub fn relay() -> Network {
+        Network {
+            stations: vec![
+                Station {
+                    name: "A".into(),
+                    ..Default::default()
+                },
+                Station {
+                    name: "H".into(),
+                    hub: true,
+                    ..Default::default()
+                },
+                Station {
+                    name: "C".into(),
+                    ..Default::default()
+                },
+            ],
+            routes: vec![
+                Route {
+                    name: "AH".into(),
+                    from_to: ("A".into(), "H".into()),
+                    travel_time: 10,
+                    tags: ["x".into()].into(),
+                    ..Default::default()
+                },
+                Route {
+                    name: "HC".into(),
+                    from_to: ("H".into(), "C".into()),
+                    travel_time: 10,
+                    tags: ["y".into()].into(),
+                    ..Default::default()
+                },
+            ],
+            packages: vec![Package {
+                name: "P".into(),
+                weight: 5,
+                from_to: ("A".into(), "C".into()),
+                ..Default::default()
+            }],
+            trains: vec![
+                Train {
+                    name: "T1".into(),
+                    capacity: 10,
+                    initial_station_name: "A".into(),
+                    tags: ["x".into()].into(),
+                    ..Default::default()
+                },
+                Train {
+                    name: "T2".into(),
+                    capacity: 10,
+                    initial_station_name: "H".into(),
+                    tags: ["y".into()].into(),
+                    ..Default::default()
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use crate::model;
+
+    #[test]
+    fn network_from_reader() {
+        let input = r#"{
+            "stations": [{ "name": "A" }, { "name": "B" }, { "name": "C" }],
+            "routes": [
+                { "name": "AB", "from_to": ["A", "B"], "travel_time": 10 },
+                { "name": "BC", "from_to": ["B", "C"], "travel_time": 10 }
+            ],
+            "packages": [{ "name": "P", "weight": 5, "from_to": ["A", "C"] }],
+            "trains": [{ "name": "T", "capacity": 5, "initial_station_name": "A" }]
+        }"#;
+
+        let network = Network::from_reader(input.as_bytes()).unwrap();
+
+        assert_eq!(network.stations.len(), 3);
+        assert_eq!(network.routes[1].from_to, ("B".into(), "C".into()));
+        assert_eq!(network.packages[0].weight, 5);
+        assert_eq!(network.trains[0].initial_station_name, "A");
+
+        let network = model::Network::try_from(network).unwrap();
+
+        assert_eq!(network.optimal_itinerary().unwrap().travel_time_used(), 20);
+    }
+
+    #[test]
+    fn parse_route() {
+        let route = parser::parse_route("AB,A,B,30").unwrap();
+
+        assert_eq!(route.from_to, ("A".into(), "B".into()));
+        assert_eq!(route.travel_time, 30);
+        assert!(!route.oneway);
+
+        let route = parser::parse_route("AB,A,B,30,oneway").unwrap();
+
+        assert!(route.oneway);
+        assert_eq!(route.to_string(), "--route AB,A,B,30,oneway");
+
+        let route = parser::parse_route("AB,A,B,30,capacity=1").unwrap();
+
+        assert!(!route.oneway);
+        assert_eq!(route.capacity, Some(1));
+        assert_eq!(route.to_string(), "--route AB,A,B,30,capacity=1");
+
+        let route = parser::parse_route("AB,A,B,30,tag=narrow-gauge").unwrap();
+
+        assert!(route.tags.contains("narrow-gauge"));
+        assert_eq!(route.to_string(), "--route AB,A,B,30,tag=narrow-gauge");
+
+        assert!(parser::parse_route("AB,A,B,30,twoway").is_err());
+        assert!(parser::parse_route("AB,A,B,30,tag=").is_err());
+        assert!(parser::parse_route("AB,A,B").is_err());
+    }
+
+    #[test]
+    fn parse_station() {
+        let station = parser::parse_station("A").unwrap();
+
+        assert_eq!(
+            (station.handling_time, station.handling_time_per_weight),
+            (0, 0)
+        );
+
+        let station = parser::parse_station("A,handling=5,handling_per_weight=2").unwrap();
+
+        assert_eq!(
+            (station.handling_time, station.handling_time_per_weight),
+            (5, 2)
+        );
+        assert_eq!(
+            station.to_string(),
+            "--station A,handling=5,handling_per_weight=2"
+        );
+
+        let station = parser::parse_station("H,hub").unwrap();
+
+        assert!(station.hub);
+        assert_eq!(station.to_string(), "--station H,hub");
+
+        assert!(parser::parse_station("A,B").is_err());
+    }
+
+    #[test]
+    fn parse_package() {
+        let package = parser::parse_package("P,5,A,B").unwrap();
+
+        assert_eq!((package.release, package.deadline), (None, None));
+
+        let package = parser::parse_package("P,5,A,B,deadline=100,release=30").unwrap();
+
+        assert_eq!((package.release, package.deadline), (Some(30), Some(100)));
+        assert_eq!(
+            package.to_string(),
+            "--package P,5,A,B,release=30,deadline=100"
+        );
+
+        let package = parser::parse_package("P,5,A,B,size.volume=3,size.pallets=1").unwrap();
+
+        assert_eq!(package.sizes.get("volume"), Some(&3));
+        assert_eq!(
+            package.to_string(),
+            "--package P,5,A,B,size.pallets=1,size.volume=3"
+        );
+
+        let package = parser::parse_package("P,5,A,B,tag=hazmat,tag=refrigerated").unwrap();
+
+        assert_eq!(package.tags.len(), 2);
+        assert_eq!(
+            package.to_string(),
+            "--package P,5,A,B,tag=hazmat,tag=refrigerated"
+        );
+
+        let package = parser::parse_package("P,5,A,B,priority=3").unwrap();
+
+        assert_eq!(package.priority, Some(3));
+        assert_eq!(package.to_string(), "--package P,5,A,B,priority=3");
+
+        assert!(parser::parse_package("P,5,A,B,release").is_err());
+        assert!(parser::parse_package("P,5,A,B,priority=high").is_err());
+        assert!(parser::parse_package("P,5,A,B,size.weight=3").is_err());
+        assert!(parser::parse_package("P,5,A,B,size.=3").is_err());
+        assert!(parser::parse_package("P,5,A,B,deadline=soon").is_err());
+    }
+
+    #[test]
+    fn parse_train() {
+        let train = parser::parse_train("T,10,A").unwrap();
+
+        assert_eq!((train.cost_per_time, train.activation_cost), (0, 0));
+
+        let train = parser::parse_train("T,10,A,activation=50,cost=3,end=B").unwrap();
+
+        assert_eq!((train.cost_per_time, train.activation_cost), (3, 50));
+        assert_eq!(train.end_station_name, Some("B".into()));
+        assert_eq!(
+            train.to_string(),
+            "--train T,10,A,cost=3,activation=50,end=B"
+        );
+
+        let train = parser::parse_train("T,10,A,capacity.volume=20").unwrap();
+
+        assert_eq!(train.capacities.get("volume"), Some(&20));
+        assert_eq!(train.to_string(), "--train T,10,A,capacity.volume=20");
+
+        let train = parser::parse_train("T,10,A,end=B,tag=refrigerated").unwrap();
+
+        assert!(train.tags.contains("refrigerated"));
+        assert_eq!(train.to_string(), "--train T,10,A,end=B,tag=refrigerated");
+
+        let train = parser::parse_train("T,10,A,max_duty=480,ready=60").unwrap();
+
+        assert_eq!((train.ready_time, train.max_duty_time), (60, Some(480)));
+        assert_eq!(train.to_string(), "--train T,10,A,ready=60,max_duty=480");
+
+        assert!(parser::parse_train("T,10,A,cost").is_err());
+        assert!(parser::parse_train("T,10,A,ready=soon").is_err());
+        assert!(parser::parse_train("T,10,A,capacity.volume=big").is_err());
+        assert!(parser::parse_train("T,10,A,fuel=3").is_err());
+    }
+
+    #[test]
+    fn network_from_reader_reject_unknown_field() {
+        let input = r#"{ "stations": [{ "name": "A", "platform": 1 }] }"#;
+
+        assert!(Network::from_reader(input.as_bytes()).is_err());
+    }
 }
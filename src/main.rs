@@ -1,16 +1,83 @@
 #[macro_use]
 extern crate derive_builder;
 
-use anyhow::Result;
+use std::thread;
+
+use anyhow::{bail, Result};
 use clap::Parser;
 
+use model::validation::{self, Diagnostic};
+
 pub mod args;
 pub mod model;
 
 fn main() -> Result<()> {
-    model::Network::try_from(args::Network::parse())?
-        .optimal_itinerary()
-        .print_output();
+    let cli = args::Cli::parse();
+
+    match cli.command {
+        Some(args::Command::Validate {
+            input,
+            output_format,
+        }) => validate(input.network()?, output_format),
+        None => solve(cli.input.network()?, &cli.solver, cli.output_format),
+    }
+}
+
+fn solve(
+    network: args::Network,
+    solver: &args::Solver,
+    output_format: args::OutputFormat,
+) -> Result<()> {
+    if solver.time_limit.is_some() && solver.search != args::Search::Heuristic {
+        bail!("--time-limit only applies to --search heuristic")
+    }
+
+    // More threads than cores only adds contention on the shared open list
+    let mut solver = solver.clone();
+    let cores = thread::available_parallelism().map_or(1, usize::from);
+
+    if let Some(threads) = solver.threads.filter(|threads| *threads > cores) {
+        eprintln!("warning: `--threads {threads}`: only {cores} core(s) available, using {cores} thread(s)");
+        solver.threads = Some(cores);
+    }
+
+    let diagnostics = validation::validate(&network);
+
+    diagnostics
+        .iter()
+        .for_each(|diagnostic| eprintln!("{diagnostic}"));
+
+    check_errors(&diagnostics)?;
+
+    model::Network::try_from(network)?
+        .optimal_itinerary_with(&solver)?
+        .print_output(output_format, &solver)?;
+
+    Ok(())
+}
+
+fn validate(network: args::Network, output_format: args::OutputFormat) -> Result<()> {
+    let diagnostics = validation::validate(&network);
+
+    match output_format {
+        args::OutputFormat::Text => diagnostics
+            .iter()
+            .for_each(|diagnostic| println!("{diagnostic}")),
+        args::OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+    }
+
+    check_errors(&diagnostics)
+}
+
+fn check_errors(diagnostics: &[Diagnostic]) -> Result<()> {
+    let error_count = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.is_error())
+        .count();
+
+    if error_count > 0 {
+        bail!("network has {error_count} error(s)")
+    }
 
     Ok(())
 }
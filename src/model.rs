@@ -1,57 +1,179 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
 use std::hash::Hash;
+use std::iter::repeat;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Error, Result};
 use itertools::zip;
 use itertools::Itertools;
-use pathfinding::prelude::dijkstra;
+use pathfinding::prelude::{astar, dijkstra};
 
 use crate::args;
 
+pub mod bound;
+pub mod component;
+pub mod cost;
+pub mod feasibility;
+pub mod heuristic;
+pub mod output;
+pub mod parallel;
 pub mod route_path;
 pub mod state;
+pub mod validation;
 
+pub use feasibility::InfeasibleError;
+pub use output::Output;
 pub use route_path::RoutePath;
 
+/// Index of a station in `Network::stations`.
+pub type StationId = usize;
+/// Index of a package in `Network::packages`.
+pub type PackageId = usize;
+/// Index of a train in `Network::trains`.
+pub type TrainId = usize;
+
 #[derive(Debug, Clone)]
 pub struct Network {
     pub stations: Vec<Station>,
     pub routes: Vec<Route>,
     pub packages: Vec<Package>,
     pub trains: Vec<Train>,
+    /// Capacity dimensions other than weight, e.g. `volume` or `pallets`, sorted by name
+    pub dimensions: Vec<String>,
 }
 
 impl Network {
+    /// Stations where trains can hand packages off to each other.
+    fn hubs(&self) -> impl Iterator<Item = &Station> {
+        self.stations.iter().filter(|station| station.is_hub)
+    }
+
+    #[cfg(test)]
     fn actions(&self) -> Vec<state::Action> {
         self.packages
             .iter()
             .flat_map(|package| package.actions())
+            .chain(self.trains.iter().filter_map(|train| train.return_action()))
             .collect_vec()
     }
 
-    pub fn optimal_itinerary(&self) -> state::Network {
-        dijkstra(
-            &state::Network::new(self),
-            |state| state.take_available_actions(),
+    pub fn optimal_itinerary(&self) -> Result<state::Network<'_>, InfeasibleError> {
+        self.optimal_itinerary_with(&args::Solver::default())
+    }
+
+    pub fn optimal_itinerary_with<'a>(
+        &'a self,
+        solver: &args::Solver,
+    ) -> Result<state::Network<'a>, InfeasibleError> {
+        let route_map = Arc::new(self.route_map());
+
+        let packages = self.undeliverable_packages(&route_map);
+
+        if !packages.is_empty() {
+            return Err(InfeasibleError { packages });
+        }
+
+        let start =
+            state::Network::with_cost_model(self, route_map, cost::cost_model(solver.objective));
+
+        let is_makespan_optimal = matches!(
+            solver.objective,
+            args::Objective::Makespan | args::Objective::MakespanThenTotal
+        );
+
+        // The best itinerary of every component, put together, is the best itinerary of the
+        // network, unless the objective trades the makespan of one for the total of the others.
+        let is_separable = solver.objective != args::Objective::MakespanThenTotal;
+
+        match solver.search {
+            args::Search::Heuristic => {
+                let deadline = solver
+                    .time_limit
+                    .map(|seconds| Instant::now() + Duration::from_secs(seconds));
+
+                heuristic::Search::new(self, start, deadline).solve()
+            }
+            search => {
+                let starts = match self.components() {
+                    components if is_separable && components.len() > 1 => components
+                        .iter()
+                        .map(|component| start.restricted_to(component))
+                        .collect_vec(),
+                    _ => vec![start],
+                };
+
+                starts
+                    .iter()
+                    .map(|start| exact_search(search, solver.threads, start))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(state::Network::merge)
+                    .map(|state| match is_makespan_optimal {
+                        true => state.proven_optimal(),
+                        false => state,
+                    })
+            }
+        }
+    }
+}
+
+/// When no itinerary completes every action, report the packages left undelivered by the
+/// furthest state explored, i.e. the one delivering the most packages.
+fn exact_search<'a>(
+    search: args::Search,
+    threads: Option<usize>,
+    start: &state::Network<'a>,
+) -> Result<state::Network<'a>, InfeasibleError> {
+    let threads = threads.unwrap_or(1);
+
+    if threads > 1 {
+        return match search {
+            args::Search::Dijkstra => parallel::Search::new(start, |_| 0).solve(threads),
+            _ => parallel::Search::new(start, |state| state.heuristic()).solve(threads),
+        };
+    }
+
+    let furthest = RefCell::new(start.clone());
+
+    let successors = |state: &state::Network<'a>| {
+        if state.delivered_count() > furthest.borrow().delivered_count() {
+            furthest.replace(state.clone());
+        }
+
+        state.take_available_actions()
+    };
+
+    match search {
+        args::Search::Dijkstra => dijkstra(start, successors, |state| state.is_success()),
+        args::Search::AStar => astar(
+            start,
+            successors,
+            |state| state.heuristic(),
             |state| state.is_success(),
-        )
-        .unwrap()
-        .0
-        .last()
-        .unwrap()
-        .clone()
+        ),
+        args::Search::Heuristic => unreachable!("heuristic search is not exact"),
     }
+    .and_then(|(states, _)| states.last().cloned())
+    .ok_or_else(|| furthest.borrow().infeasible_error())
 }
 
 impl TryFrom<args::Network> for Network {
     type Error = Error;
 
     fn try_from(input: args::Network) -> Result<Self, Self::Error> {
-        let stations = input.stations.into_iter().map(Station::from).collect_vec();
+        let stations = input
+            .stations
+            .into_iter()
+            .enumerate()
+            .map(Station::from)
+            .collect_vec();
 
         let reversed_routes = input
             .routes
             .iter()
+            .filter(|route| !route.oneway)
             .map(|route| Route::try_from((route.reverse(), stations.deref())))
             .collect::<Result<Vec<_>>>()?;
 
@@ -61,37 +183,144 @@ impl TryFrom<args::Network> for Network {
             .map(|route| Route::try_from((route, stations.deref())))
             .collect::<Result<Vec<_>>>()?;
 
-        let routes = vec![reversed_routes, routes].concat();
+        let routes = [reversed_routes, routes].concat();
+
+        let dimensions = input
+            .packages
+            .iter()
+            .flat_map(|package| package.sizes.keys())
+            .chain(
+                input
+                    .trains
+                    .iter()
+                    .flat_map(|train| train.capacities.keys()),
+            )
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect_vec();
 
-        let packages = input
+        let mut packages = input
             .packages
             .into_iter()
-            .map(|package| Package::try_from((package, stations.deref())))
+            .enumerate()
+            .map(|(id, package)| {
+                Package::try_from((id, package, stations.deref(), dimensions.deref()))
+            })
             .collect::<Result<Vec<_>>>()?;
 
-        let trains = input
+        for (twin, id) in previous_twins(&packages, Package::class) {
+            packages[id].twin = twin;
+        }
+
+        if let Some((station, package)) = stations
+            .iter()
+            .cartesian_product(&packages)
+            .find(|(station, package)| station.checked_handling_time(package).is_none())
+        {
+            return Err(anyhow!(
+                "handling time of package {} at station {} is too large",
+                package.name,
+                station.name
+            ));
+        }
+
+        let mut trains = input
             .trains
             .into_iter()
-            .map(|train| Train::try_from((train, stations.deref())))
+            .enumerate()
+            .map(|(id, train)| Train::try_from((id, train, stations.deref(), dimensions.deref())))
             .collect::<Result<Vec<_>>>()?;
 
+        for (twin, id) in previous_twins(&trains, Train::class) {
+            trains[id].twin = twin;
+        }
+
         Ok(Self {
             stations,
             routes,
             packages,
             trains,
+            dimensions,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Pair every item with the closest previous item of the same class, if any.
+fn previous_twins<T, K: Eq + Hash>(
+    items: &[T],
+    class: impl Fn(&T) -> K,
+) -> Vec<(Option<usize>, usize)> {
+    let mut last_of_class = HashMap::new();
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(id, item)| (last_of_class.insert(class(item), id), id))
+        .collect_vec()
+}
+
+/// Stations, packages and trains are compared and hashed by id only,
+/// since they are looked up and hashed all the time during the search.
+macro_rules! impl_eq_hash_by_id {
+    ($name:ident) => {
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.id == other.id
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl Hash for $name {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.id.hash(state);
+            }
+        }
+    };
+}
+
+#[derive(Debug, Clone)]
 pub struct Station {
+    pub id: StationId,
     pub name: String,
+    pub handling_time: u32,
+    pub handling_time_per_weight: u32,
+    pub is_hub: bool,
+}
+
+impl Station {
+    /// Time to pick or drop `package` at this station. It is checked to fit in a `u32`
+    /// for every package and station when the network is built.
+    pub fn handling_time(&self, package: &Package) -> u32 {
+        self.checked_handling_time(package).unwrap()
+    }
+
+    fn checked_handling_time(&self, package: &Package) -> Option<u32> {
+        self.handling_time_per_weight
+            .checked_mul(package.weight)
+            .and_then(|time| time.checked_add(self.handling_time))
+    }
 }
 
-impl From<args::Station> for Station {
-    fn from(station: args::Station) -> Self {
-        Self { name: station.name }
+impl_eq_hash_by_id!(Station);
+
+impl From<(StationId, args::Station)> for Station {
+    fn from((id, station): (StationId, args::Station)) -> Self {
+        let args::Station {
+            name,
+            handling_time,
+            handling_time_per_weight,
+            hub,
+        } = station;
+
+        Self {
+            id,
+            name,
+            handling_time,
+            handling_time_per_weight,
+            is_hub: hub,
+        }
     }
 }
 
@@ -100,6 +329,9 @@ pub struct Route {
     pub name: String,
     pub from_to: (Station, Station),
     pub travel_time: u32,
+    pub capacity: Option<u32>,
+    /// Tags a train needs to use the route
+    pub tags: BTreeSet<String>,
 }
 
 impl Route {
@@ -108,6 +340,8 @@ impl Route {
             name: format!("{}#id", station.name),
             from_to: (station.clone(), station.clone()),
             travel_time: 0,
+            capacity: None,
+            tags: BTreeSet::new(),
         }
     }
 }
@@ -136,11 +370,11 @@ impl Route {
     }
 
     pub fn is_from(&self, station: &Station) -> bool {
-        self.from().name == station.name
+        self.from() == station
     }
 
     pub fn is_to(&self, station: &Station) -> bool {
-        self.to().name == station.name
+        self.to() == station
     }
 }
 
@@ -152,6 +386,9 @@ impl TryFrom<(args::Route, &[Station])> for Route {
             name,
             from_to: (from, to),
             travel_time,
+            oneway: _,
+            capacity,
+            tags,
         } = route;
 
         let from_to = (find_station(stations, from)?, find_station(stations, to)?);
@@ -160,15 +397,28 @@ impl TryFrom<(args::Route, &[Station])> for Route {
             name,
             from_to,
             travel_time,
+            capacity,
+            tags,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct Package {
+    pub id: PackageId,
     pub name: String,
     pub weight: u32,
     pub from_to: (Station, Station),
+    pub release: Option<u32>,
+    pub deadline: Option<u32>,
+    /// Size in every dimension of `Network::dimensions`, 0 when not given
+    pub sizes: Vec<u32>,
+    /// Tags a train needs to carry the package
+    pub tags: BTreeSet<String>,
+    /// Weight of the delivery time in the weighted completion objective
+    pub priority: u32,
+    /// Previous package that is interchangeable with this one
+    pub twin: Option<PackageId>,
 }
 
 impl Package {
@@ -180,57 +430,220 @@ impl Package {
         &self.from_to.1
     }
 
-    fn actions(&self) -> Vec<state::Action> {
-        vec![
-            state::Action::Pick(self.clone(), self.from().clone()),
-            state::Action::Drop(self.clone(), self.to().clone()),
+    /// Packages of the same class only differ by name.
+    #[allow(clippy::type_complexity)]
+    fn class(
+        &self,
+    ) -> (
+        u32,
+        (Station, Station),
+        Option<u32>,
+        Option<u32>,
+        Vec<u32>,
+        BTreeSet<String>,
+        u32,
+    ) {
+        (
+            self.weight,
+            self.from_to.clone(),
+            self.release,
+            self.deadline,
+            self.sizes.clone(),
+            self.tags.clone(),
+            self.priority,
+        )
+    }
+
+    fn actions(&self) -> [state::Action; 2] {
+        [
+            state::Action::Pick(self.id, self.from().id),
+            state::Action::Drop(self.id),
         ]
     }
 }
 
-impl TryFrom<(args::Package, &[Station])> for Package {
+impl_eq_hash_by_id!(Package);
+
+impl TryFrom<(PackageId, args::Package, &[Station], &[String])> for Package {
     type Error = Error;
 
-    fn try_from((package, stations): (args::Package, &[Station])) -> Result<Self, Self::Error> {
+    fn try_from(
+        (id, package, stations, dimensions): (PackageId, args::Package, &[Station], &[String]),
+    ) -> Result<Self, Self::Error> {
         let args::Package {
             name,
             weight,
             from_to: (from, to),
+            release,
+            deadline,
+            sizes,
+            tags,
+            priority,
         } = package;
 
         let from_to = (find_station(stations, from)?, find_station(stations, to)?);
+        let sizes = dimensions
+            .iter()
+            .map(|dimension| sizes.get(dimension).copied().unwrap_or(0))
+            .collect_vec();
 
         Ok(Self {
+            id,
             name,
             weight,
             from_to,
+            release,
+            deadline,
+            sizes,
+            tags,
+            priority: priority.unwrap_or(1),
+            twin: None,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct Train {
+    pub id: TrainId,
     pub name: String,
     pub capacity: u32,
     pub initial_station: Station,
+    pub cost_per_time: u32,
+    pub activation_cost: u32,
+    pub end_station: Option<Station>,
+    /// Capacity in every dimension of `Network::dimensions`, `None` when unlimited
+    pub capacities: Vec<Option<u32>>,
+    /// Tags of the packages and routes the train is fit for
+    pub tags: BTreeSet<String>,
+    /// Time the shift starts, where the train clock starts
+    pub ready_time: u32,
+    /// Longest time the train may run from `ready_time` until it is done
+    pub max_duty_time: Option<u32>,
+    /// Previous train that is interchangeable with this one
+    pub twin: Option<TrainId>,
 }
 
-impl TryFrom<(args::Train, &[Station])> for Train {
+impl Train {
+    /// Trains of the same class only differ by name.
+    #[allow(clippy::type_complexity)]
+    fn class(
+        &self,
+    ) -> (
+        u32,
+        Station,
+        Option<Station>,
+        u32,
+        u32,
+        Vec<Option<u32>>,
+        BTreeSet<String>,
+        u32,
+        Option<u32>,
+    ) {
+        (
+            self.capacity,
+            self.initial_station.clone(),
+            self.end_station.clone(),
+            self.cost_per_time,
+            self.activation_cost,
+            self.capacities.clone(),
+            self.tags.clone(),
+            self.ready_time,
+            self.max_duty_time,
+        )
+    }
+
+    /// Time the train must be done by, or `None` when its shift has no limit. A shift ending
+    /// past the last time a `u32` holds is cut there.
+    pub fn shift_end(&self) -> Option<u32> {
+        self.max_duty_time
+            .map(|max_duty_time| self.ready_time.saturating_add(max_duty_time))
+    }
+
+    /// Whether the train may carry `package` and it fits when nothing else is on board.
+    pub fn can_carry(&self, package: &Package) -> bool {
+        self.is_compatible(package) && self.can_fit(package, 0, &[])
+    }
+
+    /// Whether the train has every tag `package` needs.
+    fn is_compatible(&self, package: &Package) -> bool {
+        package.tags.is_subset(&self.tags)
+    }
+
+    /// Whether the train has every tag `route` needs.
+    fn can_use(&self, route: &Route) -> bool {
+        route.tags.is_subset(&self.tags)
+    }
+
+    /// Whether `package` fits on top of the `load` and `sizes` already on board, in every
+    /// dimension. Dimensions missing from `sizes` are empty.
+    fn can_fit(&self, package: &Package, load: u32, sizes: &[u32]) -> bool {
+        let fits = |amount: u32, on_board: u32, capacity: u32| {
+            amount
+                .checked_add(on_board)
+                .is_some_and(|total| total <= capacity)
+        };
+
+        let is_light_enough = fits(package.weight, load, self.capacity);
+
+        let is_small_enough = zip(&self.capacities, &package.sizes)
+            .zip(sizes.iter().chain(repeat(&0)))
+            .all(|((capacity, size), on_board)| {
+                capacity.is_none_or(|capacity| fits(*size, *on_board, capacity))
+            });
+
+        is_light_enough && is_small_enough
+    }
+
+    fn return_action(&self) -> Option<state::Action> {
+        self.end_station
+            .as_ref()
+            .map(|_| state::Action::Return(self.id))
+    }
+}
+
+impl_eq_hash_by_id!(Train);
+
+impl TryFrom<(TrainId, args::Train, &[Station], &[String])> for Train {
     type Error = Error;
 
-    fn try_from((train, stations): (args::Train, &[Station])) -> Result<Self, Self::Error> {
+    fn try_from(
+        (id, train, stations, dimensions): (TrainId, args::Train, &[Station], &[String]),
+    ) -> Result<Self, Self::Error> {
         let args::Train {
             name,
             capacity,
             initial_station_name,
+            cost_per_time,
+            activation_cost,
+            end_station_name,
+            capacities,
+            tags,
+            ready_time,
+            max_duty_time,
         } = train;
 
         let initial_station = find_station(stations, initial_station_name)?;
+        let end_station = end_station_name
+            .map(|station_name| find_station(stations, station_name))
+            .transpose()?;
+        let capacities = dimensions
+            .iter()
+            .map(|dimension| capacities.get(dimension).copied())
+            .collect_vec();
 
         Ok(Self {
+            id,
             name,
             capacity,
             initial_station,
+            cost_per_time,
+            activation_cost,
+            end_station,
+            capacities,
+            tags,
+            ready_time,
+            max_duty_time,
+            twin: None,
         })
     }
 }
@@ -244,6 +657,12 @@ pub struct Instruction {
     pub picked_package: Vec<Package>,
     #[builder(default)]
     pub dropped_package: Vec<Package>,
+    /// Packages left at a hub for another train
+    #[builder(default)]
+    pub transferred_package: Vec<Package>,
+    /// Time the train is held at the departure station before `begin_at`
+    #[builder(default)]
+    pub waited: u32,
 }
 
 impl Instruction {
@@ -259,18 +678,23 @@ impl Instruction {
                 train: self.train,
                 route: self.route,
                 picked_package: self.picked_package,
-                dropped_package: vec![self.dropped_package, other.dropped_package].concat(),
+                dropped_package: [self.dropped_package, other.dropped_package].concat(),
+                transferred_package: [self.transferred_package, other.transferred_package].concat(),
+                waited: self.waited + other.waited,
             }]
         } else if is_same_train
             && self.dropped_package.is_empty()
+            && self.transferred_package.is_empty()
             && self.route.from().clone() == other.route.from().clone()
         {
             vec![Instruction {
-                begin_at: self.begin_at,
+                begin_at: other.begin_at,
                 train: self.train,
                 route: other.route,
                 dropped_package: other.dropped_package,
-                picked_package: vec![self.picked_package, other.picked_package].concat(),
+                transferred_package: other.transferred_package,
+                picked_package: [self.picked_package, other.picked_package].concat(),
+                waited: self.waited + other.waited,
             }]
         } else {
             vec![self, other]
@@ -288,30 +712,33 @@ fn find_station(stations: &[Station], station_name: String) -> Result<Station> {
 
 impl std::fmt::Display for Instruction {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let picked_package_name = format!(
-            "[{}]",
-            self.picked_package
-                .iter()
-                .map(|package| package.name.clone())
-                .join(", ")
-        );
+        let package_names = |packages: &[Package]| {
+            format!(
+                "[{}]",
+                packages
+                    .iter()
+                    .map(|package| package.name.clone())
+                    .join(", ")
+            )
+        };
 
-        let dropped_package_name = format!(
-            "[{}]",
-            self.dropped_package
-                .iter()
-                .map(|package| package.name.clone())
-                .join(", ")
-        );
-        let val = vec![
+        let mut val = vec![
             ("W", self.begin_at.to_string()),
             ("T", self.train.name.clone()),
             ("N1", self.route.from().name.clone()),
-            ("P1", picked_package_name),
+            ("P1", package_names(&self.picked_package)),
             ("N2", self.route.to().name.clone()),
-            ("P2", dropped_package_name),
+            ("P2", package_names(&self.dropped_package)),
         ];
 
+        if !self.transferred_package.is_empty() {
+            val.push(("Transferred", package_names(&self.transferred_package)));
+        }
+
+        if self.waited > 0 {
+            val.push(("Waited", self.waited.to_string()));
+        }
+
         let mut str = "";
         for (field, value) in val {
             fmt.write_str(str)?;
@@ -345,26 +772,134 @@ pub mod case {
     from_args!(multiple_packages_small_train);
     from_args!(multiple_packages_big_train);
     from_args!(multiple_packages_islands);
+    from_args!(one_way);
+    from_args!(release);
+    from_args!(deadline);
+    from_args!(handling);
+    from_args!(single_track);
+    from_args!(single_track_detour);
+    from_args!(helper_train);
+    from_args!(spare_train);
+    from_args!(costly_trains);
+    from_args!(depot);
+    from_args!(hub);
+    from_args!(volume);
+    from_args!(tags);
+    from_args!(priority);
+    from_args!(shift);
+    from_args!(relay);
 }
 
 #[cfg(test)]
 pub mod test {
     use super::*;
 
+    use std::cell::Cell;
+
     macro_rules! test_solve_train_network {
         ($case_name:ident, $expected_time:literal) => {
             #[test]
             fn $case_name() {
                 let network = case::$case_name();
 
-                assert_eq!(
-                    network.optimal_itinerary().travel_time_used(),
-                    $expected_time
+                for (search, threads) in [args::Search::Dijkstra, args::Search::AStar]
+                    .into_iter()
+                    .cartesian_product([None, Some(4)])
+                {
+                    let solver = args::Solver {
+                        search,
+                        threads,
+                        ..Default::default()
+                    };
+
+                    assert_eq!(
+                        network
+                            .optimal_itinerary_with(&solver)
+                            .unwrap()
+                            .travel_time_used(),
+                        $expected_time
+                    );
+                }
+
+                assert!(
+                    expanded_states(&network, args::Search::AStar)
+                        <= expanded_states(&network, args::Search::Dijkstra)
                 );
             }
         };
     }
 
+    fn expanded_states(network: &Network, search: args::Search) -> usize {
+        let start = state::Network::new(network, Arc::new(network.route_map()));
+        let expanded = Cell::new(0);
+
+        fn successors<'a>(
+            state: &state::Network<'a>,
+            expanded: &Cell<usize>,
+        ) -> Vec<(state::Network<'a>, u64)> {
+            expanded.set(expanded.get() + 1);
+            state.take_available_actions()
+        }
+
+        match search {
+            args::Search::Dijkstra => dijkstra(
+                &start,
+                |state| successors(state, &expanded),
+                |state| state.is_success(),
+            ),
+            args::Search::AStar => astar(
+                &start,
+                |state| successors(state, &expanded),
+                |state| state.heuristic(),
+                |state| state.is_success(),
+            ),
+            args::Search::Heuristic => unreachable!("heuristic search does not expand states"),
+        }
+        .unwrap();
+
+        expanded.get()
+    }
+
+    #[test]
+    fn twins() {
+        let mut input = args::case::multiple_packages_small_train();
+        input.trains.push(args::Train {
+            name: "T2".into(),
+            capacity: 10,
+            ..input.trains[0].clone()
+        });
+        input.trains.push(args::Train {
+            name: "T3".into(),
+            ..input.trains[0].clone()
+        });
+
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(
+            network
+                .packages
+                .iter()
+                .map(|package| package.twin)
+                .collect_vec(),
+            vec![None, Some(0)]
+        );
+        assert_eq!(
+            network.trains.iter().map(|train| train.twin).collect_vec(),
+            vec![None, None, Some(0)]
+        );
+        assert_eq!(network.optimal_itinerary().unwrap().travel_time_used(), 10);
+    }
+
+    #[test]
+    fn astar_expand_less_states() {
+        for network in [case::diverge(), case::helper_train()] {
+            let dijkstra = expanded_states(&network, args::Search::Dijkstra);
+            let astar = expanded_states(&network, args::Search::AStar);
+
+            assert!(astar < dijkstra, "astar: {astar}, dijkstra: {dijkstra}");
+        }
+    }
+
     test_solve_train_network!(direct, 20);
     test_solve_train_network!(choice, 20);
     test_solve_train_network!(islands, 10);
@@ -372,4 +907,327 @@ pub mod test {
     test_solve_train_network!(multiple_packages_small_train, 30);
     test_solve_train_network!(multiple_packages_big_train, 10);
     test_solve_train_network!(multiple_packages_islands, 20);
+    test_solve_train_network!(one_way, 20);
+    test_solve_train_network!(release, 50);
+    test_solve_train_network!(deadline, 170);
+    test_solve_train_network!(handling, 35);
+    test_solve_train_network!(single_track, 20);
+    test_solve_train_network!(single_track_detour, 12);
+    test_solve_train_network!(helper_train, 25);
+    test_solve_train_network!(spare_train, 20);
+    test_solve_train_network!(costly_trains, 10);
+    test_solve_train_network!(depot, 40);
+    test_solve_train_network!(hub, 20);
+    test_solve_train_network!(volume, 30);
+    test_solve_train_network!(tags, 20);
+    test_solve_train_network!(priority, 30);
+    test_solve_train_network!(shift, 50);
+    test_solve_train_network!(relay, 20);
+
+    macro_rules! test_objective {
+        ($test_name:ident, $case_name:ident, $objective:expr, $expected_times:expr) => {
+            #[test]
+            fn $test_name() {
+                let network = case::$case_name();
+
+                for search in [args::Search::Dijkstra, args::Search::AStar] {
+                    let solver = args::Solver {
+                        search,
+                        objective: $objective,
+                        ..Default::default()
+                    };
+                    let state = network.optimal_itinerary_with(&solver).unwrap();
+
+                    assert_eq!(
+                        (state.travel_time_used(), state.total_travel_time_used()),
+                        $expected_times
+                    );
+                }
+            }
+        };
+    }
+
+    test_objective!(
+        helper_train_makespan,
+        helper_train,
+        args::Objective::Makespan,
+        (25, 35)
+    );
+    test_objective!(
+        helper_train_total,
+        helper_train,
+        args::Objective::Total,
+        (30, 30)
+    );
+    test_objective!(
+        helper_train_makespan_then_total,
+        helper_train,
+        args::Objective::MakespanThenTotal,
+        (25, 35)
+    );
+    test_objective!(
+        spare_train_makespan_then_total,
+        spare_train,
+        args::Objective::MakespanThenTotal,
+        (20, 30)
+    );
+    test_objective!(
+        multiple_packages_islands_total,
+        multiple_packages_islands,
+        args::Objective::Total,
+        (20, 30)
+    );
+
+    #[test]
+    fn priority_weighted_completion() {
+        let network = case::priority();
+
+        for search in [
+            args::Search::Dijkstra,
+            args::Search::AStar,
+            args::Search::Heuristic,
+        ] {
+            let solver = args::Solver {
+                search,
+                objective: args::Objective::WeightedCompletion,
+                ..Default::default()
+            };
+            let state = network.optimal_itinerary_with(&solver).unwrap();
+
+            assert_eq!(state.delivery_times(), vec![Some(30), Some(10)]);
+            assert_eq!(state.weighted_completion_time(), 80);
+            assert_eq!(state.travel_time_used(), 30);
+        }
+    }
+
+    #[test]
+    fn hub_weighted_completion() {
+        let mut input = args::case::hub();
+        input.stations[0].hub = true;
+        let network = Network::try_from(input).unwrap();
+
+        for search in [args::Search::Dijkstra, args::Search::AStar] {
+            let solver = args::Solver {
+                search,
+                objective: args::Objective::WeightedCompletion,
+                ..Default::default()
+            };
+            let state = network.optimal_itinerary_with(&solver).unwrap();
+
+            assert_eq!(state.weighted_completion_time(), 40);
+        }
+    }
+
+    #[test]
+    fn shift_time_used() {
+        let network = case::shift();
+
+        for objective in [args::Objective::Makespan, args::Objective::Total] {
+            let solver = args::Solver {
+                objective,
+                ..Default::default()
+            };
+            let state = network.optimal_itinerary_with(&solver).unwrap();
+
+            // T2 runs from 30 to 50, T1 never leaves.
+            assert_eq!(
+                (state.travel_time_used(), state.total_travel_time_used()),
+                (50, 20)
+            );
+            assert_eq!(state.instructions()[0].begin_at, 30);
+        }
+
+        let mut input = args::case::shift();
+        input.trains[0].max_duty_time = Some(20);
+
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(network.optimal_itinerary().unwrap().travel_time_used(), 20);
+    }
+
+    #[test]
+    fn multiple_packages_islands_instructions() {
+        let network = case::multiple_packages_islands();
+        let state = network.optimal_itinerary().unwrap();
+
+        assert!(state.is_success());
+        assert_eq!(
+            state
+                .instructions()
+                .iter()
+                .map(|instruction| instruction.train.name.as_str())
+                .collect_vec(),
+            vec!["T1", "T2"]
+        );
+        assert_eq!(state.lower_bounds().optimal, Some(20));
+    }
+
+    #[test]
+    fn hub_transfer() {
+        let network = case::hub();
+        let state = network.optimal_itinerary().unwrap();
+
+        // Either package can be handed off, T2 carries it on from H.
+        let transfers = state
+            .instructions()
+            .into_iter()
+            .filter(|instruction| !instruction.transferred_package.is_empty())
+            .map(|instruction| {
+                (
+                    instruction.train.name,
+                    instruction.route.to().name.clone(),
+                    instruction.transferred_package.len(),
+                )
+            })
+            .collect_vec();
+
+        assert_eq!(transfers, vec![("T1".into(), "H".into(), 1)]);
+
+        let mut input = args::case::hub();
+        input.stations[1].hub = false;
+
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(network.optimal_itinerary().unwrap().travel_time_used(), 30);
+    }
+
+    #[test]
+    fn can_fit_large() {
+        let mut network = case::volume();
+        network.packages[0].weight = u32::MAX;
+        network.packages[1].sizes[0] = u32::MAX;
+
+        let train = &network.trains[0];
+
+        assert!(!train.can_fit(&network.packages[0], 1, &[0]));
+        assert!(!train.can_fit(&network.packages[1], 0, &[1]));
+        assert!(!train.can_carry(&network.packages[1]));
+    }
+
+    #[test]
+    fn conflicting_packages() {
+        let mut input = args::case::priority();
+        input.trains[0].max_duty_time = Some(25);
+        let network = Network::try_from(input).unwrap();
+
+        for search in [
+            args::Search::Dijkstra,
+            args::Search::AStar,
+            args::Search::Heuristic,
+        ] {
+            let solver = args::Solver {
+                search,
+                ..Default::default()
+            };
+            let error = network.optimal_itinerary_with(&solver).unwrap_err();
+
+            assert_eq!(error.packages.len(), 1);
+            assert_eq!(error.packages[0].reason, feasibility::Reason::Conflicting);
+        }
+    }
+
+    #[test]
+    fn shift_large() {
+        let mut input = args::case::direct();
+        input.trains[0].ready_time = 4_000_000_000;
+        input.trains[0].max_duty_time = Some(4_000_000_000);
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(network.trains[0].shift_end(), Some(u32::MAX));
+        assert_eq!(
+            network.optimal_itinerary().unwrap().travel_time_used(),
+            4_000_000_020
+        );
+    }
+
+    #[test]
+    fn handling_time_large() {
+        let mut input = args::case::handling();
+        input.packages[0].weight = u32::MAX;
+
+        assert_eq!(
+            Network::try_from(input).unwrap_err().to_string(),
+            "handling time of package P at station C is too large"
+        );
+    }
+
+    #[test]
+    fn volume_utilization() {
+        let network = case::volume();
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(
+            output.trains[0].to_string(),
+            "T = T, Time used = 30, Cost = 0, Peak load = weight 5/10, volume 3/4"
+        );
+
+        let mut input = args::case::volume();
+        input.trains[0].capacities.clear();
+
+        let network = Network::try_from(input).unwrap();
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(
+            output.trains[0].to_string(),
+            "T = T, Time used = 10, Cost = 0, Peak load = weight 10/10, volume 6"
+        );
+    }
+
+    #[test]
+    fn tags_restrict_trains_and_routes() {
+        let network = case::tags();
+        let state = network.optimal_itinerary().unwrap();
+
+        // T1 could take the short route, but only T2 may carry the package.
+        let routes = state
+            .instructions()
+            .into_iter()
+            .map(|instruction| (instruction.train.name, instruction.route.name))
+            .collect_vec();
+
+        assert_eq!(
+            routes,
+            vec![("T2".into(), "AB".into()), ("T2".into(), "BC".into())]
+        );
+
+        let mut input = args::case::tags();
+        input.trains[1].tags.insert("narrow-gauge".into());
+
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(network.optimal_itinerary().unwrap().travel_time_used(), 10);
+    }
+
+    #[test]
+    fn costly_trains_cost() {
+        let network = case::costly_trains();
+
+        for search in [args::Search::Dijkstra, args::Search::AStar] {
+            let solver = args::Solver {
+                search,
+                objective: args::Objective::Makespan,
+                ..Default::default()
+            };
+            let state = network.optimal_itinerary_with(&solver).unwrap();
+
+            assert_eq!((state.travel_time_used(), state.total_cost()), (10, 100));
+
+            let solver = args::Solver {
+                search,
+                objective: args::Objective::Cost,
+                ..Default::default()
+            };
+            let output = network.optimal_itinerary_with(&solver).unwrap().output();
+
+            assert_eq!((output.total_time_used, output.total_cost), (20, 20));
+            assert_eq!(
+                output
+                    .trains
+                    .iter()
+                    .map(|train| (train.train.as_str(), train.time_used, train.cost))
+                    .collect_vec(),
+                vec![("T1", 0, 0), ("T2", 20, 20), ("T3", 0, 0)]
+            );
+        }
+    }
 }
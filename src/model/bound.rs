@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::route_path::RouteMap;
+use super::*;
+
+/// Lower bounds of the optimal total time used, to tell how good an itinerary is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LowerBounds {
+    /// Latest time any single package can be delivered, or any train can return to its end station
+    pub longest_delivery: u32,
+    /// Weight times travel time of every package, shared by the capacity of every train
+    pub workload: u32,
+    /// Total time used of an itinerary proven to have the optimal total time used
+    pub optimal: Option<u32>,
+}
+
+impl LowerBounds {
+    pub fn best(&self) -> u32 {
+        self.optimal
+            .unwrap_or_else(|| self.longest_delivery.max(self.workload))
+    }
+}
+
+impl Display for LowerBounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (longest delivery: {}, workload: {}",
+            self.best(),
+            self.longest_delivery,
+            self.workload
+        )?;
+
+        if let Some(optimal) = self.optimal {
+            write!(f, ", optimal: {optimal}")?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl Network {
+    /// A train carries at most its capacity at any time, so all trains together need at least
+    /// the sum of every package's weight times its shortest travel time, over their capacity.
+    pub(super) fn workload_lower_bound(&self, route_map: &RouteMap) -> u32 {
+        let capacity: u64 = self
+            .trains
+            .iter()
+            .map(|train| u64::from(train.capacity))
+            .sum();
+
+        let workload: u64 = self
+            .packages
+            .iter()
+            .filter_map(|package| {
+                route_map.get(&package.from_to).map(|route_path| {
+                    u64::from(package.weight) * u64::from(route_path.travel_time())
+                })
+            })
+            .sum();
+
+        match capacity {
+            0 => 0,
+            _ => u32::try_from(workload.div_ceil(capacity)).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use crate::args;
+
+    #[test]
+    fn workload_lower_bound() {
+        for (network, expected_bound) in [
+            (case::direct(), 20),
+            (case::multiple_packages_small_train(), 20),
+            (case::helper_train(), 10),
+        ] {
+            assert_eq!(
+                network.workload_lower_bound(&network.route_map()),
+                expected_bound
+            );
+        }
+    }
+
+    #[test]
+    fn workload_lower_bound_large() {
+        let mut input = args::case::direct();
+        input.routes[0].travel_time = 100_000;
+        input.packages[0].weight = 50_000;
+        input.trains[0].capacity = 50_000;
+
+        let network = Network::try_from(input).unwrap();
+
+        assert_eq!(network.workload_lower_bound(&network.route_map()), 100_010);
+    }
+
+    #[test]
+    fn best() {
+        let bounds = LowerBounds {
+            longest_delivery: 20,
+            workload: 30,
+            optimal: None,
+        };
+
+        assert_eq!(bounds.best(), 30);
+        assert_eq!(
+            bounds.to_string(),
+            "30 (longest delivery: 20, workload: 30)"
+        );
+
+        let bounds = LowerBounds {
+            optimal: Some(40),
+            ..bounds
+        };
+
+        assert_eq!(bounds.best(), 40);
+    }
+}
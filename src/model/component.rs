@@ -0,0 +1,86 @@
+use pathfinding::undirected::connected_components::connected_components;
+
+use super::*;
+
+/// Stations linked by routes, with the packages and trains that stay among them.
+/// Trains of different components never meet, so each component can be solved on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub stations: Vec<StationId>,
+    pub packages: Vec<PackageId>,
+    pub trains: Vec<TrainId>,
+}
+
+impl Network {
+    /// Split the network along its routes, regardless of their direction.
+    ///
+    /// A package whose start and destination are in different components belongs to none
+    /// of them. Such a package is reported by `undeliverable_packages`.
+    pub fn components(&self) -> Vec<Component> {
+        let station_ids = self.stations.iter().map(|station| station.id).collect_vec();
+
+        let neighbours = |id: &StationId| {
+            self.routes
+                .iter()
+                .filter_map(|route| match (route.from().id, route.to().id) {
+                    (from, to) if from == *id => Some(to),
+                    (from, to) if to == *id => Some(from),
+                    _ => None,
+                })
+                .collect_vec()
+        };
+
+        connected_components(&station_ids, neighbours)
+            .into_iter()
+            .map(|stations| {
+                let is_inside = |station: &Station| stations.contains(&station.id);
+
+                Component {
+                    stations: stations.iter().copied().sorted().collect_vec(),
+                    packages: self
+                        .packages
+                        .iter()
+                        .filter(|package| is_inside(package.from()) && is_inside(package.to()))
+                        .map(|package| package.id)
+                        .collect_vec(),
+                    trains: self
+                        .trains
+                        .iter()
+                        .filter(|train| is_inside(&train.initial_station))
+                        .map(|train| train.id)
+                        .collect_vec(),
+                }
+            })
+            .sorted_by_key(|component| component.stations[0])
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn components() {
+        assert_eq!(
+            case::multiple_packages_islands().components(),
+            vec![
+                Component {
+                    stations: vec![0, 1],
+                    packages: vec![0],
+                    trains: vec![0],
+                },
+                Component {
+                    stations: vec![2, 3],
+                    packages: vec![1],
+                    trains: vec![1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn components_one_way() {
+        assert_eq!(case::one_way().components().len(), 1);
+    }
+}
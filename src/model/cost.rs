@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use super::state;
+use crate::args;
+
+/// What the search minimizes. `cost` must never decrease as actions are taken,
+/// and `heuristic` must never overestimate the cost still to come.
+pub trait CostModel: Debug + Send + Sync {
+    fn cost(&self, state: &state::Network) -> u64;
+
+    fn heuristic(&self, state: &state::Network) -> u64;
+}
+
+pub fn cost_model(objective: args::Objective) -> Arc<dyn CostModel> {
+    match objective {
+        args::Objective::Makespan => Arc::new(Makespan),
+        args::Objective::Total => Arc::new(TotalTime),
+        args::Objective::Cost => Arc::new(TotalCost),
+        args::Objective::WeightedCompletion => Arc::new(WeightedCompletion),
+        args::Objective::MakespanThenTotal => Arc::new(Lexicographic {
+            primary: Makespan,
+            secondary: TotalTime,
+        }),
+    }
+}
+
+/// Time until the last train finishes.
+#[derive(Debug, Clone, Copy)]
+pub struct Makespan;
+
+impl CostModel for Makespan {
+    fn cost(&self, state: &state::Network) -> u64 {
+        state.travel_time_used().into()
+    }
+
+    fn heuristic(&self, state: &state::Network) -> u64 {
+        state
+            .travel_time_lower_bound()
+            .saturating_sub(state.travel_time_used())
+            .into()
+    }
+}
+
+/// Sum of the time every train is running.
+#[derive(Debug, Clone, Copy)]
+pub struct TotalTime;
+
+impl CostModel for TotalTime {
+    fn cost(&self, state: &state::Network) -> u64 {
+        state.total_travel_time_used()
+    }
+
+    fn heuristic(&self, state: &state::Network) -> u64 {
+        state
+            .total_travel_time_lower_bound()
+            .saturating_sub(state.total_travel_time_used())
+    }
+}
+
+/// Sum of every train's operating cost.
+#[derive(Debug, Clone, Copy)]
+pub struct TotalCost;
+
+impl CostModel for TotalCost {
+    fn cost(&self, state: &state::Network) -> u64 {
+        state.total_cost()
+    }
+
+    fn heuristic(&self, state: &state::Network) -> u64 {
+        state
+            .total_cost_lower_bound()
+            .saturating_sub(state.total_cost())
+    }
+}
+
+/// Sum of the time every package is delivered, times its priority. Undelivered packages are
+/// charged for the time they have been on their way, so moving a package around never comes
+/// for free.
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedCompletion;
+
+impl CostModel for WeightedCompletion {
+    fn cost(&self, state: &state::Network) -> u64 {
+        state.weighted_elapsed_time()
+    }
+
+    fn heuristic(&self, state: &state::Network) -> u64 {
+        state
+            .weighted_completion_time_lower_bound()
+            .saturating_sub(state.weighted_elapsed_time())
+    }
+}
+
+/// Minimize `primary` first, then break ties with `secondary`.
+///
+/// Both costs are packed into one number, with `secondary` in the lower 32 bits. `primary`
+/// must fit in 32 bits, and `secondary` saturates there so it never spills into `primary`;
+/// only ties between such large secondaries are lost.
+#[derive(Debug)]
+pub struct Lexicographic<P, S> {
+    pub primary: P,
+    pub secondary: S,
+}
+
+impl<P: CostModel, S: CostModel> CostModel for Lexicographic<P, S> {
+    fn cost(&self, state: &state::Network) -> u64 {
+        pack(self.primary.cost(state), self.secondary.cost(state))
+    }
+
+    fn heuristic(&self, state: &state::Network) -> u64 {
+        pack(
+            self.primary.heuristic(state),
+            self.secondary.heuristic(state),
+        )
+    }
+}
+
+fn pack(primary: u64, secondary: u64) -> u64 {
+    debug_assert!(primary <= u64::from(u32::MAX));
+
+    (primary << 32) + secondary.min(u64::from(u32::MAX))
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn pack_large_secondary() {
+        let max = u64::from(u32::MAX);
+
+        assert!(pack(1, max + 1) < pack(2, 0));
+        assert!(pack(1, 0) < pack(1, max));
+        assert_eq!(pack(1, max + 1), pack(1, max));
+    }
+}
@@ -0,0 +1,373 @@
+use std::fmt::Display;
+
+use super::route_path::{Distances, RouteMap, TrainRouteMap};
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    TooHeavy,
+    TooLarge,
+    Incompatible,
+    UnreachablePickup,
+    UnreachableDestination,
+    MissedDeadline,
+    OutsideShift,
+    /// Deliverable alone, but not along with the other packages
+    Conflicting,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Reason::TooHeavy => "too heavy for every train that can reach its start station",
+            Reason::TooLarge => {
+                "too large in some dimension for every train that can reach its start station"
+            }
+            Reason::Incompatible => {
+                "no train that can reach its start station is allowed to carry it"
+            }
+            Reason::UnreachablePickup => "start station is unreachable from every train",
+            Reason::UnreachableDestination => {
+                "destination station is unreachable from its start station"
+            }
+            Reason::MissedDeadline => "cannot arrive at its destination before its deadline",
+            Reason::OutsideShift => {
+                "cannot be delivered within the shift of any train that can carry it"
+            }
+            Reason::Conflicting => "cannot be delivered along with the other packages",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndeliverablePackage {
+    pub package: Package,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfeasibleError {
+    pub packages: Vec<UndeliverablePackage>,
+}
+
+impl Display for InfeasibleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.packages.is_empty() {
+            return f.write_str("no feasible itinerary found");
+        }
+
+        write!(f, "cannot deliver {} package(s):", self.packages.len())?;
+
+        for UndeliverablePackage { package, reason } in &self.packages {
+            write!(f, "\n  {}: {reason}", package.name)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for InfeasibleError {}
+
+impl Network {
+    pub fn undeliverable_packages(&self, route_map: &Arc<RouteMap>) -> Vec<UndeliverablePackage> {
+        let train_route_maps = self.train_route_maps(route_map);
+
+        self.packages
+            .iter()
+            .filter_map(|package| {
+                self.undeliverable_reason(package, &train_route_maps)
+                    .map(|reason| UndeliverablePackage {
+                        package: package.clone(),
+                        reason,
+                    })
+            })
+            .collect_vec()
+    }
+
+    fn undeliverable_reason(
+        &self,
+        package: &Package,
+        train_route_maps: &[TrainRouteMap],
+    ) -> Option<Reason> {
+        let reaching_trains = zip(&self.trains, train_route_maps)
+            .filter_map(|(train, (_, distances))| {
+                distances
+                    .get(&train.initial_station, package.from())
+                    .map(|travel_time| (train, distances.deref(), travel_time))
+            })
+            .collect_vec();
+
+        let compatible_trains = reaching_trains
+            .iter()
+            .filter(|(train, _, _)| train.is_compatible(package))
+            .collect_vec();
+
+        // With hubs, trains may hand the package off, so it can travel on the routes of any
+        // train allowed to carry it. Otherwise one train carries it all the way on its own routes.
+        let travel_time = match self.hubs().next() {
+            Some(_) => self.travel_time_through(&package.from_to, |route| {
+                self.trains
+                    .iter()
+                    .any(|train| train.is_compatible(package) && train.can_use(route))
+            }),
+            None => compatible_trains
+                .iter()
+                .filter_map(|(_, distances, _)| distances.get(package.from(), package.to()))
+                .min(),
+        };
+
+        let carrying_trains = compatible_trains
+            .iter()
+            .filter(|(train, _, _)| train.can_carry(package))
+            .collect_vec();
+
+        let pick_at = |train: &Train, travel_time: u32| {
+            (train.ready_time.saturating_add(travel_time))
+                .max(package.release.unwrap_or(0))
+                .saturating_add(package.from().handling_time(package))
+        };
+
+        let earliest_pick = carrying_trains
+            .iter()
+            .map(|(train, _, travel_time)| pick_at(train, *travel_time))
+            .min();
+
+        let is_within_shift = carrying_trains
+            .iter()
+            .any(|(train, distances, travel_time)| {
+                self.is_within_shift(train, distances, package, pick_at(train, *travel_time))
+            });
+
+        match (earliest_pick, travel_time) {
+            _ if reaching_trains.is_empty() => Some(Reason::UnreachablePickup),
+            _ if compatible_trains.is_empty() => Some(Reason::Incompatible),
+            (_, None) => Some(Reason::UnreachableDestination),
+            (None, _)
+                if compatible_trains
+                    .iter()
+                    .any(|(train, _, _)| train.capacity >= package.weight) =>
+            {
+                Some(Reason::TooLarge)
+            }
+            (None, _) => Some(Reason::TooHeavy),
+            (Some(pick_at), Some(travel_time))
+                if pick_at
+                    .saturating_add(travel_time)
+                    .saturating_add(package.to().handling_time(package))
+                    > package.deadline.unwrap_or(u32::MAX) =>
+            {
+                Some(Reason::MissedDeadline)
+            }
+            _ if !is_within_shift => Some(Reason::OutsideShift),
+            _ => None,
+        }
+    }
+
+    /// Whether `train` can pick `package` at `pick_at`, deliver it and be back at its end
+    /// station before its shift ends. With hubs, another train may carry the package on,
+    /// so only the pickup has to fit in the shift.
+    fn is_within_shift(
+        &self,
+        train: &Train,
+        distances: &Distances,
+        package: &Package,
+        pick_at: u32,
+    ) -> bool {
+        let done_at = match self.hubs().next() {
+            Some(_) => Some(pick_at),
+            None => distances
+                .get(package.from(), package.to())
+                .map(|travel_time| {
+                    pick_at
+                        .saturating_add(travel_time)
+                        .saturating_add(package.to().handling_time(package))
+                })
+                .and_then(|deliver_at| match &train.end_station {
+                    Some(end_station) => distances
+                        .get(package.to(), end_station)
+                        .map(|travel_time| deliver_at.saturating_add(travel_time)),
+                    None => Some(deliver_at),
+                }),
+        };
+
+        // Times saturate at `u32::MAX`, which no itinerary can reach
+        let is_in_time = done_at.is_none_or(|done_at| done_at < u32::MAX);
+
+        is_in_time
+            && train
+                .shift_end()
+                .is_none_or(|shift_end| done_at.is_some_and(|done_at| done_at <= shift_end))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use crate::args;
+
+    fn reasons(input: args::Network) -> Vec<(String, Reason)> {
+        let network = Network::try_from(input).unwrap();
+
+        network
+            .undeliverable_packages(&Arc::new(network.route_map()))
+            .into_iter()
+            .map(|undeliverable| (undeliverable.package.name, undeliverable.reason))
+            .collect_vec()
+    }
+
+    #[test]
+    fn feasible() {
+        assert_eq!(reasons(args::case::diverge()), vec![]);
+    }
+
+    #[test]
+    fn too_heavy() {
+        let mut input = args::case::direct();
+        input.packages[0].weight = 6;
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::TooHeavy)]);
+    }
+
+    #[test]
+    fn too_large() {
+        let mut input = args::case::direct();
+        input.packages[0].sizes.insert("volume".into(), 30);
+        input.trains[0].capacities.insert("volume".into(), 20);
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::TooLarge)]);
+    }
+
+    #[test]
+    fn incompatible() {
+        let mut input = args::case::direct();
+        input.packages[0].tags.insert("hazmat".into());
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::Incompatible)]);
+    }
+
+    #[test]
+    fn unreachable_destination_by_tags() {
+        let mut input = args::case::direct();
+        input.routes[1].tags.insert("narrow-gauge".into());
+
+        assert_eq!(
+            reasons(input),
+            vec![("P".into(), Reason::UnreachableDestination)]
+        );
+    }
+
+    #[test]
+    fn unreachable_destination_without_hub() {
+        let mut input = args::case::direct();
+        input.routes[0].tags.insert("x".into());
+        input.routes[1].tags.insert("y".into());
+        input.trains = vec![
+            args::Train {
+                name: "T1".into(),
+                capacity: 5,
+                initial_station_name: "A".into(),
+                tags: ["x".into()].into(),
+                ..Default::default()
+            },
+            args::Train {
+                name: "T2".into(),
+                capacity: 5,
+                initial_station_name: "B".into(),
+                tags: ["y".into()].into(),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            reasons(input.clone()),
+            vec![("P".into(), Reason::UnreachableDestination)]
+        );
+
+        input.stations[1].hub = true;
+
+        assert_eq!(reasons(input), vec![]);
+    }
+
+    #[test]
+    fn outside_shift() {
+        let mut input = args::case::shift();
+        input.trains.pop();
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::OutsideShift)]);
+
+        let mut input = args::case::direct();
+        input.trains[0].ready_time = 10;
+        input.packages[0].deadline = Some(25);
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::MissedDeadline)]);
+    }
+
+    #[test]
+    fn outside_shift_large() {
+        let mut input = args::case::direct();
+        input.trains[0].ready_time = 4_000_000_000;
+        input.trains[0].max_duty_time = Some(4_000_000_000);
+
+        assert_eq!(reasons(input), vec![]);
+
+        let mut input = args::case::direct();
+        input.trains[0].ready_time = u32::MAX - 5;
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::OutsideShift)]);
+    }
+
+    #[test]
+    fn unreachable_pickup() {
+        let mut input = args::case::islands();
+        input.packages[0].from_to = ("C".into(), "B".into());
+
+        assert_eq!(
+            reasons(input),
+            vec![("P".into(), Reason::UnreachablePickup)]
+        );
+    }
+
+    #[test]
+    fn unreachable_destination() {
+        let mut input = args::case::islands();
+        input.packages[0].from_to = ("A".into(), "C".into());
+
+        assert_eq!(
+            reasons(input),
+            vec![("P".into(), Reason::UnreachableDestination)]
+        );
+    }
+
+    #[test]
+    fn missed_deadline() {
+        let mut input = args::case::direct();
+        input.packages[0].release = Some(10);
+        input.packages[0].deadline = Some(29);
+
+        assert_eq!(reasons(input), vec![("P".into(), Reason::MissedDeadline)]);
+    }
+
+    #[test]
+    fn optimal_itinerary_infeasible() {
+        let mut input = args::case::multiple_packages_islands();
+        input.packages[0].weight = 10;
+        input.packages[1].from_to = ("A2".into(), "B1".into());
+
+        let network = Network::try_from(input).unwrap();
+
+        let error = network.optimal_itinerary().unwrap_err();
+
+        assert_eq!(
+            error
+                .packages
+                .iter()
+                .map(|undeliverable| (undeliverable.package.name.as_str(), undeliverable.reason))
+                .collect_vec(),
+            vec![
+                ("P1", Reason::TooHeavy),
+                ("P2", Reason::UnreachableDestination)
+            ]
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use std::time::Instant;
+
+use super::*;
+
+/// Actions of every train, in the order each train takes them. Return actions are left out.
+type Plan = Vec<Vec<state::Action>>;
+
+#[derive(Debug, Clone)]
+struct Candidate<'a> {
+    plan: Plan,
+    state: state::Network<'a>,
+    cost: u64,
+}
+
+/// Anytime search for networks too large to solve exactly. Packages are inserted one by one
+/// into the train and positions where they cost the least, then packages are relocated and
+/// swapped between trains for as long as it improves the itinerary, or until `deadline`.
+///
+/// The itinerary found is feasible but not necessarily optimal.
+pub struct Search<'a> {
+    network: &'a Network,
+    start: state::Network<'a>,
+    deadline: Option<Instant>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(network: &'a Network, start: state::Network<'a>, deadline: Option<Instant>) -> Self {
+        Self {
+            network,
+            start,
+            deadline,
+        }
+    }
+
+    /// When the greedy insertion cannot place every package, report the packages it left out.
+    pub fn solve(&self) -> Result<state::Network<'a>, InfeasibleError> {
+        let mut best = self.insert_all()?;
+
+        while let Some(candidate) = self.improve(&best) {
+            best = candidate;
+        }
+
+        Ok(best.state)
+    }
+
+    /// Insert every package in turn, skipping any that cannot be placed.
+    fn insert_all(&self) -> Result<Candidate<'a>, InfeasibleError> {
+        let packages = self
+            .network
+            .packages
+            .iter()
+            .sorted_by_key(|package| {
+                (
+                    package.deadline.unwrap_or(u32::MAX),
+                    package.release.unwrap_or(0),
+                )
+            })
+            .collect_vec();
+
+        let mut best = self
+            .evaluate(vec![vec![]; self.network.trains.len()])
+            .ok_or_else(|| self.start.infeasible_error())?;
+        let mut is_complete = true;
+
+        for package in packages {
+            match self.best_insertion(&best.plan, package, &self.all_trains()) {
+                Some(candidate) => best = candidate,
+                None => is_complete = false,
+            }
+        }
+
+        match is_complete {
+            true => Ok(best),
+            false => Err(best.state.infeasible_error()),
+        }
+    }
+
+    /// First move that lowers the cost of `best`, or `None` at a local optimum or after `deadline`.
+    fn improve(&self, best: &Candidate<'a>) -> Option<Candidate<'a>> {
+        let is_better = |candidate: &Candidate| candidate.cost < best.cost;
+
+        for package in &self.network.packages {
+            if self.is_timed_out() {
+                return None;
+            }
+
+            let relocated =
+                self.best_insertion(&remove(&best.plan, package), package, &self.all_trains());
+
+            if let Some(candidate) = relocated.filter(is_better) {
+                return Some(candidate);
+            }
+        }
+
+        for (package, other) in self.network.packages.iter().tuple_combinations() {
+            let (train, other_train) = (train_of(&best.plan, package), train_of(&best.plan, other));
+
+            if train == other_train {
+                continue;
+            }
+
+            if self.is_timed_out() {
+                return None;
+            }
+
+            let plan = remove(&remove(&best.plan, package), other);
+
+            let swapped = self
+                .best_insertion(&plan, package, &[other_train])
+                .and_then(|candidate| self.best_insertion(&candidate.plan, other, &[train]));
+
+            if let Some(candidate) = swapped.filter(is_better) {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    /// Cheapest way to pick and drop `package` by one of `trains`, keeping the rest of `plan`.
+    ///
+    /// With hubs, `package` can also be relayed: one of `trains` picks it and leaves it at a hub,
+    /// then another picks it there and drops it. The first train's part is placed where it costs
+    /// the least, then the second train's part.
+    fn best_insertion(
+        &self,
+        plan: &Plan,
+        package: &Package,
+        trains: &[usize],
+    ) -> Option<Candidate<'a>> {
+        let [pick, drop] = package.actions();
+
+        let carried = trains
+            .iter()
+            .filter_map(|train| self.best_insertion_by(plan, *train, [pick, drop]));
+
+        let relayed = self
+            .network
+            .hubs()
+            .filter(|hub| *hub != package.from() && *hub != package.to())
+            .cartesian_product(trains)
+            .filter_map(|(hub, train)| {
+                let transfer = state::Action::Transfer(package.id, hub.id);
+
+                self.best_insertion_by(plan, *train, [pick, transfer])
+                    .map(|candidate| (hub, train, candidate))
+            })
+            .flat_map(|(hub, train, candidate)| {
+                let pick_at_hub = state::Action::Pick(package.id, hub.id);
+
+                trains
+                    .iter()
+                    .filter(move |other_train| *other_train != train)
+                    .filter_map(move |other_train| {
+                        self.best_insertion_by(&candidate.plan, *other_train, [pick_at_hub, drop])
+                    })
+            });
+
+        self.cheapest(carried.chain(relayed))
+    }
+
+    /// Cheapest way to insert `first` then `second` into the actions of `train`.
+    fn best_insertion_by(
+        &self,
+        plan: &Plan,
+        train: usize,
+        [first, second]: [state::Action; 2],
+    ) -> Option<Candidate<'a>> {
+        let len = plan[train].len();
+
+        let candidates = (0..=len)
+            .flat_map(|first_at| (first_at..=len).map(move |second_at| (first_at, second_at)))
+            .map(|(first_at, second_at)| {
+                let mut plan = plan.clone();
+
+                plan[train].insert(second_at, second);
+                plan[train].insert(first_at, first);
+
+                plan
+            })
+            .filter_map(|plan| self.evaluate(plan));
+
+        self.cheapest(candidates)
+    }
+
+    /// Cheapest of `candidates`. After `deadline`, settle for the first one found, so the
+    /// remaining packages are still inserted, only not where they cost the least.
+    fn cheapest(&self, candidates: impl Iterator<Item = Candidate<'a>>) -> Option<Candidate<'a>> {
+        let mut best: Option<Candidate<'a>> = None;
+
+        for candidate in candidates {
+            if best.as_ref().is_none_or(|best| candidate.cost < best.cost) {
+                best = Some(candidate);
+            }
+
+            if self.is_timed_out() {
+                break;
+            }
+        }
+
+        best
+    }
+
+    fn evaluate(&self, plan: Plan) -> Option<Candidate<'a>> {
+        let full_plan = zip(&plan, &self.network.trains)
+            .map(|(actions, train)| {
+                actions
+                    .iter()
+                    .cloned()
+                    .chain(train.return_action())
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        let mut state = self.start.clone();
+
+        state.take_plan(&full_plan).then(|| Candidate {
+            plan,
+            cost: state.cost(),
+            state,
+        })
+    }
+
+    fn all_trains(&self) -> Vec<usize> {
+        (0..self.network.trains.len()).collect_vec()
+    }
+
+    fn is_timed_out(&self) -> bool {
+        self.deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false)
+    }
+}
+
+fn remove(plan: &Plan, package: &Package) -> Plan {
+    plan.iter()
+        .map(|actions| {
+            actions
+                .iter()
+                .filter(|action| action.package_id() != Some(package.id))
+                .cloned()
+                .collect_vec()
+        })
+        .collect_vec()
+}
+
+fn train_of(plan: &Plan, package: &Package) -> usize {
+    plan.iter()
+        .position(|actions| {
+            actions
+                .iter()
+                .any(|action| action.package_id() == Some(package.id))
+        })
+        .unwrap()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::args;
+
+    fn solve(network: &Network, deadline: Option<Instant>) -> state::Network<'_> {
+        let start = state::Network::new(network, Arc::new(network.route_map()));
+
+        Search::new(network, start, deadline).solve().unwrap()
+    }
+
+    #[test]
+    fn solve_every_case() {
+        for network in [
+            case::direct(),
+            case::choice(),
+            case::islands(),
+            case::diverge(),
+            case::multiple_packages_small_train(),
+            case::multiple_packages_big_train(),
+            case::multiple_packages_islands(),
+            case::one_way(),
+            case::release(),
+            case::deadline(),
+            case::handling(),
+            case::single_track(),
+            case::single_track_detour(),
+            case::helper_train(),
+            case::spare_train(),
+            case::costly_trains(),
+            case::depot(),
+            case::hub(),
+            case::volume(),
+            case::tags(),
+            case::priority(),
+            case::shift(),
+            case::relay(),
+        ] {
+            let optimal = network.optimal_itinerary().unwrap();
+            let state = solve(&network, None);
+
+            assert!(state.is_success());
+            assert!(state.travel_time_used() >= optimal.travel_time_used());
+            assert!(state.lower_bounds().best() <= optimal.travel_time_used());
+        }
+    }
+
+    #[test]
+    fn solve_optimal() {
+        for (network, expected_time) in [
+            (case::direct(), 20),
+            (case::diverge(), 160),
+            (case::multiple_packages_small_train(), 30),
+            (case::helper_train(), 25),
+        ] {
+            assert_eq!(solve(&network, None).travel_time_used(), expected_time);
+        }
+    }
+
+    #[test]
+    fn solve_hub() {
+        for network in [case::hub(), case::relay()] {
+            let state = solve(&network, None);
+
+            assert!(state.is_success());
+            assert_eq!(state.cost(), 20);
+            assert_eq!(state.gap(), 0.0);
+        }
+    }
+
+    #[test]
+    fn solve_timed_out() {
+        let network = case::multiple_packages_small_train();
+
+        let state = solve(&network, Some(Instant::now()));
+
+        assert!(state.is_success());
+        assert!(state.travel_time_used() >= 30);
+    }
+
+    #[test]
+    fn solve_timed_out_large() {
+        // S0----S1----...----S9, 3 trains and 60 packages between stations spread along the line
+        let stations = (0..10).map(|index| format!("S{index}")).collect_vec();
+        let input = args::Network {
+            stations: stations
+                .iter()
+                .map(|name| args::Station {
+                    name: name.clone(),
+                    ..Default::default()
+                })
+                .collect_vec(),
+            routes: stations
+                .iter()
+                .tuple_windows()
+                .map(|(from, to)| args::Route {
+                    name: format!("{from}{to}"),
+                    from_to: (from.clone(), to.clone()),
+                    travel_time: 10,
+                    ..Default::default()
+                })
+                .collect_vec(),
+            packages: (0..60)
+                .map(|index| args::Package {
+                    name: format!("P{index}"),
+                    weight: 5,
+                    from_to: (
+                        stations[index * 7 % 10].clone(),
+                        stations[(index * 3 + 1) % 10].clone(),
+                    ),
+                    ..Default::default()
+                })
+                .collect_vec(),
+            trains: (0..3)
+                .map(|index| args::Train {
+                    name: format!("T{index}"),
+                    capacity: 10,
+                    initial_station_name: stations[index * 4].clone(),
+                    ..Default::default()
+                })
+                .collect_vec(),
+        };
+        let network = Network::try_from(input).unwrap();
+
+        let start = Instant::now();
+        let state = solve(&network, Some(start + Duration::from_millis(200)));
+
+        assert!(state.is_success());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::bound::LowerBounds;
+use super::*;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Output {
+    pub instructions: Vec<InstructionOutput>,
+    pub total_time_used: u32,
+    pub total_train_time_used: u64,
+    pub trains: Vec<TrainOutput>,
+    pub packages: Vec<PackageOutput>,
+    pub total_cost: u64,
+    pub weighted_completion_time: u64,
+    pub lower_bounds: LowerBounds,
+    pub gap: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstructionOutput {
+    pub begin_at: u32,
+    pub train: String,
+    pub from: String,
+    pub to: String,
+    pub route: String,
+    pub picked_packages: Vec<String>,
+    pub dropped_packages: Vec<String>,
+    pub transferred_packages: Vec<String>,
+    pub waited: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrainOutput {
+    pub train: String,
+    pub time_used: u32,
+    pub cost: u64,
+    /// Peak load against capacity, in weight first and then in every other dimension
+    pub utilization: Vec<Utilization>,
+}
+
+impl Display for TrainOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "T = {}, Time used = {}, Cost = {}, Peak load = {}",
+            self.train,
+            self.time_used,
+            self.cost,
+            self.utilization.iter().join(", ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Utilization {
+    pub dimension: String,
+    pub peak_load: u32,
+    /// `None` when the train has no limit in this dimension
+    pub capacity: Option<u32>,
+}
+
+impl Display for Utilization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.dimension, self.peak_load)?;
+
+        if let Some(capacity) = self.capacity {
+            write!(f, "/{capacity}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageOutput {
+    pub package: String,
+    pub priority: u32,
+    /// Time the package is dropped at its destination
+    pub delivered_at: u32,
+}
+
+impl Display for PackageOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "P = {}, Delivered at = {}, Priority = {}",
+            self.package, self.delivered_at, self.priority
+        )
+    }
+}
+
+impl From<&Instruction> for InstructionOutput {
+    fn from(instruction: &Instruction) -> Self {
+        Self {
+            begin_at: instruction.begin_at,
+            train: instruction.train.name.clone(),
+            from: instruction.route.from().name.clone(),
+            to: instruction.route.to().name.clone(),
+            route: instruction.route.name.clone(),
+            picked_packages: package_names(&instruction.picked_package),
+            dropped_packages: package_names(&instruction.dropped_package),
+            transferred_packages: package_names(&instruction.transferred_package),
+            waited: instruction.waited,
+        }
+    }
+}
+
+fn package_names(packages: &[Package]) -> Vec<String> {
+    packages
+        .iter()
+        .map(|package| package.name.clone())
+        .collect_vec()
+}
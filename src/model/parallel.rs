@@ -0,0 +1,279 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use super::*;
+
+/// State waiting in the open list, with its cost so far and its estimated final cost. Its key
+/// is computed once, outside the lock, since it is costly.
+struct Node<'a> {
+    estimate: u64,
+    cost: u64,
+    key: state::Key,
+    state: state::Network<'a>,
+}
+
+/// Lowest estimate first, then the deepest node, since it is closer to a complete itinerary.
+impl<'a> Ord for Node<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then(self.cost.cmp(&other.cost))
+    }
+}
+
+impl<'a> PartialOrd for Node<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> PartialEq for Node<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a> Eq for Node<'a> {}
+
+struct Frontier<'a> {
+    open: BinaryHeap<Node<'a>>,
+    /// Lowest cost every state was reached with, so a worse path to it is not expanded
+    best_costs: HashMap<state::Key, u64>,
+    /// Best complete itinerary found so far
+    incumbent: Option<Node<'a>>,
+    /// Workers expanding a node, which may still push cheaper nodes
+    expanding: usize,
+    /// State delivering the most packages so far, reported when there is no itinerary
+    furthest: state::Network<'a>,
+}
+
+/// Best-first search expanded by many threads at once, sharing the open list and the cost of
+/// every reached state. With `heuristic` never overestimating the remaining cost, the result is
+/// as good as the sequential A* result.
+///
+/// The search stops once no node left, nor any node being expanded, can beat the best
+/// itinerary found, so expanding nodes out of order never loses the optimum.
+pub struct Search<'a, H> {
+    frontier: Mutex<Frontier<'a>>,
+    changed: Condvar,
+    heuristic: H,
+}
+
+impl<'a> Frontier<'a> {
+    /// Cost of the best complete itinerary found so far, which any other node must beat.
+    fn bound(&self) -> u64 {
+        self.incumbent
+            .as_ref()
+            .map(|node| node.cost)
+            .unwrap_or(u64::MAX)
+    }
+}
+
+impl<'a, H> Search<'a, H>
+where
+    H: Fn(&state::Network<'a>) -> u64 + Sync,
+{
+    pub fn new(start: &state::Network<'a>, heuristic: H) -> Self {
+        let node = Node {
+            estimate: heuristic(start),
+            cost: 0,
+            key: start.key(),
+            state: start.clone(),
+        };
+
+        Self {
+            frontier: Mutex::new(Frontier {
+                best_costs: HashMap::from([(node.key.clone(), 0)]),
+                open: BinaryHeap::from([node]),
+                incumbent: None,
+                expanding: 0,
+                furthest: start.clone(),
+            }),
+            changed: Condvar::new(),
+            heuristic,
+        }
+    }
+
+    /// When no itinerary completes every action, report the packages left undelivered by the
+    /// furthest state explored.
+    pub fn solve(self, threads: usize) -> Result<state::Network<'a>, InfeasibleError> {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| self.work());
+            }
+        });
+
+        let frontier = self.frontier.into_inner().unwrap();
+
+        frontier
+            .incumbent
+            .map(|node| node.state)
+            .ok_or_else(|| frontier.furthest.infeasible_error())
+    }
+
+    fn work(&self) {
+        let mut frontier = self.frontier.lock().unwrap();
+
+        loop {
+            let bound = frontier.bound();
+
+            let is_promising = frontier
+                .open
+                .peek()
+                .is_some_and(|node| node.estimate < bound);
+
+            if !is_promising {
+                if frontier.expanding == 0 {
+                    self.changed.notify_all();
+                    return;
+                }
+
+                frontier = self.changed.wait(frontier).unwrap();
+                continue;
+            }
+
+            let node = frontier.open.pop().unwrap();
+
+            let is_stale = frontier
+                .best_costs
+                .get(&node.key)
+                .is_some_and(|cost| *cost < node.cost);
+
+            if is_stale {
+                continue;
+            }
+
+            if node.state.delivered_count() > frontier.furthest.delivered_count() {
+                frontier.furthest = node.state.clone();
+            }
+
+            frontier.expanding += 1;
+            drop(frontier);
+
+            let successors = (!node.state.is_success()).then(|| self.successors(&node));
+
+            frontier = self.frontier.lock().unwrap();
+            frontier.expanding -= 1;
+
+            // Another worker may have found a better itinerary while the lock was released
+            let bound = frontier.bound();
+            let mut is_changed = frontier.expanding == 0;
+
+            match successors {
+                None if node.cost < bound => {
+                    frontier.incumbent = Some(node);
+                    is_changed = true;
+                }
+                None => {}
+                Some(successors) => {
+                    for successor in successors {
+                        let is_cheaper = successor.estimate < bound
+                            && frontier
+                                .best_costs
+                                .get(&successor.key)
+                                .is_none_or(|cost| successor.cost < *cost);
+
+                        if is_cheaper {
+                            frontier
+                                .best_costs
+                                .insert(successor.key.clone(), successor.cost);
+                            frontier.open.push(successor);
+                            is_changed = true;
+                        }
+                    }
+                }
+            }
+
+            // Only wake idle workers when there is something new for them to do or to stop on
+            if is_changed {
+                self.changed.notify_all();
+            }
+        }
+    }
+
+    fn successors(&self, node: &Node<'a>) -> Vec<Node<'a>> {
+        node.state
+            .take_available_actions()
+            .into_iter()
+            .map(|(state, step_cost)| {
+                let cost = node.cost + step_cost;
+
+                Node {
+                    estimate: cost + (self.heuristic)(&state),
+                    cost,
+                    key: state.key(),
+                    state,
+                }
+            })
+            .collect_vec()
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use std::sync::Arc;
+
+    #[test]
+    fn solve_every_case() {
+        for network in [
+            case::direct(),
+            case::choice(),
+            case::islands(),
+            case::diverge(),
+            case::multiple_packages_small_train(),
+            case::multiple_packages_big_train(),
+            case::multiple_packages_islands(),
+            case::one_way(),
+            case::release(),
+            case::deadline(),
+            case::handling(),
+            case::single_track(),
+            case::single_track_detour(),
+            case::helper_train(),
+            case::spare_train(),
+            case::costly_trains(),
+            case::depot(),
+            case::hub(),
+            case::volume(),
+            case::tags(),
+            case::priority(),
+            case::shift(),
+            case::relay(),
+        ] {
+            let optimal = network.optimal_itinerary().unwrap().travel_time_used();
+            let start = state::Network::new(&network, Arc::new(network.route_map()));
+
+            for threads in [1, 4] {
+                let state = Search::new(&start, |state| state.heuristic())
+                    .solve(threads)
+                    .unwrap();
+
+                assert!(state.is_success());
+                assert_eq!(state.travel_time_used(), optimal);
+
+                let state = Search::new(&start, |_| 0).solve(threads).unwrap();
+
+                assert_eq!(state.travel_time_used(), optimal);
+            }
+        }
+    }
+
+    #[test]
+    fn solve_infeasible() {
+        let mut network = case::direct();
+        network.trains[0].capacity = 0;
+
+        let start = state::Network::new(&network, Arc::new(network.route_map()));
+
+        let error = Search::new(&start, |state| state.heuristic())
+            .solve(4)
+            .unwrap_err();
+
+        assert_eq!(error.packages.len(), 1);
+    }
+}
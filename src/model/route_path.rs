@@ -4,7 +4,7 @@ use std::ops::Deref;
 use anyhow::Result;
 use itertools::zip;
 use itertools::Itertools;
-use pathfinding::prelude::{build_path, dijkstra_all};
+use pathfinding::prelude::{build_path, dijkstra, dijkstra_all};
 
 use super::*;
 
@@ -16,7 +16,9 @@ pub struct RoutePath {
 
 impl RoutePath {
     pub fn travel_time(&self) -> u32 {
-        self.routes.iter().map(|route| route.travel_time).sum()
+        self.routes.iter().fold(0, |travel_time, route| {
+            travel_time.saturating_add(route.travel_time)
+        })
     }
 }
 
@@ -49,21 +51,130 @@ impl TryFrom<(&[Station], &[Route])> for RoutePath {
     }
 }
 
-pub type RouteMap = HashMap<(Station, Station), RoutePath>;
+pub type RouteMap = HashMap<(Station, Station), Arc<RoutePath>>;
+
+/// Route map of a train and the distances in it.
+pub type TrainRouteMap = (Arc<RouteMap>, Arc<Distances>);
+
+/// Shortest travel time between every two stations, indexed by station id.
+#[derive(Debug, Clone)]
+pub struct Distances(Vec<Vec<Option<u32>>>);
+
+impl Distances {
+    pub fn get(&self, from: &Station, to: &Station) -> Option<u32> {
+        self.0[from.id][to.id]
+    }
+}
 
 impl Network {
+    /// Route map through every route, whichever train is allowed on it.
     pub fn route_map(&self) -> RouteMap {
-        let all_shortest_route_paths = self.shortest_route_paths();
+        self.route_map_through(&self.routes)
+    }
 
-        HashMap::from_iter(zip(
-            all_shortest_route_paths
-                .iter()
-                .map(|route_path| route_path.from_to.clone()),
-            all_shortest_route_paths.iter().cloned(),
-        ))
+    /// Route map of every train, only through the routes it is allowed on. Trains allowed on
+    /// every route share `route_map`, and trains allowed on the same routes share one map.
+    pub fn train_route_maps(&self, route_map: &Arc<RouteMap>) -> Vec<TrainRouteMap> {
+        let mut route_maps = HashMap::new();
+
+        self.trains
+            .iter()
+            .map(|train| {
+                let routes = self
+                    .routes
+                    .iter()
+                    .filter(|route| train.can_use(route))
+                    .cloned()
+                    .collect_vec();
+
+                route_maps
+                    .entry(routes)
+                    .or_insert_with_key(|routes| {
+                        let route_map = match routes.len() == self.routes.len() {
+                            true => route_map.clone(),
+                            false => Arc::new(self.route_map_through(routes)),
+                        };
+                        let distances = Arc::new(self.distances(&route_map));
+
+                        (route_map, distances)
+                    })
+                    .clone()
+            })
+            .collect_vec()
+    }
+
+    /// Distances of every package, indexed by package id, only through the routes some train
+    /// able to carry it is allowed on, whichever trains relay it at hubs. Packages any train may
+    /// carry anywhere share `distances`.
+    pub fn package_distances(&self, distances: &Arc<Distances>) -> Vec<Arc<Distances>> {
+        let mut package_distances = HashMap::new();
+
+        self.packages
+            .iter()
+            .map(|package| {
+                let routes = self
+                    .routes
+                    .iter()
+                    .filter(|route| {
+                        self.trains
+                            .iter()
+                            .any(|train| train.can_carry(package) && train.can_use(route))
+                    })
+                    .cloned()
+                    .collect_vec();
+
+                package_distances
+                    .entry(routes)
+                    .or_insert_with_key(|routes| match routes.len() == self.routes.len() {
+                        true => distances.clone(),
+                        false => Arc::new(self.distances(&self.route_map_through(routes))),
+                    })
+                    .clone()
+            })
+            .collect_vec()
     }
 
-    fn shortest_route_paths(&self) -> Vec<RoutePath> {
+    fn route_map_through(&self, routes: &[Route]) -> RouteMap {
+        HashMap::from_iter(
+            self.shortest_route_paths(routes)
+                .into_iter()
+                .map(|route_path| (route_path.from_to.clone(), Arc::new(route_path))),
+        )
+    }
+
+    pub fn distances(&self, route_map: &RouteMap) -> Distances {
+        let mut distances = vec![vec![None; self.stations.len()]; self.stations.len()];
+
+        for ((from, to), route_path) in route_map {
+            distances[from.id][to.id] = Some(route_path.travel_time());
+        }
+
+        Distances(distances)
+    }
+
+    /// Shortest travel time from a station to another, only through routes `can_use` allows.
+    pub fn travel_time_through(
+        &self,
+        from_to: &(Station, Station),
+        can_use: impl Fn(&Route) -> bool,
+    ) -> Option<u32> {
+        let (from, to) = from_to;
+
+        dijkstra(
+            from,
+            |station| {
+                routes_from(&self.routes, station)
+                    .into_iter()
+                    .filter(|route| can_use(route))
+                    .map(|route| (route.to().clone(), route.travel_time))
+                    .collect_vec()
+            },
+            |station| station == to,
+        )
+        .map(|(_, travel_time)| travel_time)
+    }
+
+    fn shortest_route_paths(&self, routes: &[Route]) -> Vec<RoutePath> {
         let self_route_paths = self
             .stations
             .iter()
@@ -76,43 +187,181 @@ impl Network {
         let out_route_paths = self
             .stations
             .iter()
-            .flat_map(|station| self.shortest_route_paths_from(station))
+            .flat_map(|station| shortest_route_paths_from(station, routes))
             .unique()
             .collect_vec();
 
-        vec![self_route_paths, out_route_paths].concat()
+        [self_route_paths, out_route_paths].concat()
     }
+}
 
-    fn shortest_route_paths_from(&self, from: &Station) -> Vec<RoutePath> {
-        let reachable_stations = dijkstra_all(from, |to| self.reachable_stations(to));
+fn shortest_route_paths_from(from: &Station, routes: &[Route]) -> Vec<RoutePath> {
+    let reachable_stations = dijkstra_all(from, |to| reachable_stations(to, routes));
 
-        reachable_stations
-            .keys()
-            .map(|to| build_path(to, &reachable_stations))
-            .map(|station_seq| {
-                RoutePath::try_from((station_seq.deref(), self.routes.deref())).unwrap()
-            })
-            .collect_vec()
+    reachable_stations
+        .keys()
+        .map(|to| build_path(to, &reachable_stations))
+        .map(|station_seq| RoutePath::try_from((station_seq.deref(), routes)).unwrap())
+        .collect_vec()
+}
+
+fn routes_from<'r>(routes: &'r [Route], station: &Station) -> Vec<&'r Route> {
+    routes
+        .iter()
+        .filter(|route| route.is_from(station))
+        .collect_vec()
+}
+
+fn reachable_stations(station: &Station, routes: &[Route]) -> Vec<(Station, u32)> {
+    let outward_routes = routes_from(routes, station);
+
+    let available_stations = outward_routes
+        .iter()
+        .map(|route| route.to())
+        .cloned()
+        .collect_vec();
+
+    let travel_time = outward_routes.into_iter().map(|route| route.travel_time);
+
+    zip(available_stations, travel_time).collect_vec()
+}
+
+/// A route occupied by a train, from the time it enters the route until it leaves it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reservation {
+    pub route: Route,
+    pub begin_at: u32,
+    pub end_at: u32,
+}
+
+impl Route {
+    /// Earliest time, not before `ready_at`, a train can enter this route
+    /// without going over its capacity.
+    pub fn earliest_departure(&self, ready_at: u32, reservations: &[Reservation]) -> u32 {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity as usize,
+            None => return ready_at,
+        };
+
+        let reservations = reservations
+            .iter()
+            .filter(|reservation| &reservation.route == self)
+            .collect_vec();
+
+        let is_free = |begin_at: u32| {
+            let end_at = begin_at.saturating_add(self.travel_time);
+
+            reservations
+                .iter()
+                .filter(|reservation| {
+                    reservation.begin_at < end_at && begin_at < reservation.end_at
+                })
+                .count()
+                < capacity
+        };
+
+        std::iter::once(ready_at)
+            .chain(
+                reservations
+                    .iter()
+                    .map(|reservation| reservation.end_at)
+                    .filter(|end_at| *end_at > ready_at)
+                    .sorted(),
+            )
+            .find(|begin_at| is_free(*begin_at))
+            .unwrap()
     }
+}
+
+impl RoutePath {
+    /// Time a train leaving at `begin_at` is held before each route of this path.
+    pub fn held(&self, begin_at: u32, reservations: &[Reservation]) -> Vec<u32> {
+        let mut ready_at = begin_at;
 
-    fn routes_from(&self, station: &Station) -> Vec<&Route> {
         self.routes
             .iter()
-            .filter(|route| route.is_from(station))
+            .map(|route| {
+                let departure = route.earliest_departure(ready_at, reservations);
+                let held = departure - ready_at;
+
+                ready_at = departure.saturating_add(route.travel_time);
+
+                held
+            })
             .collect_vec()
     }
+}
+
+impl Network {
+    /// Route path with the earliest arrival for `train` leaving `from` at `begin_at`,
+    /// together with the time it is held before each route. The train may wait at a station
+    /// for a route to be free or take a longer path around it, among the routes it is allowed on.
+    ///
+    /// `to` must be reachable from `from`, otherwise the search never ends.
+    pub fn earliest_route_path(
+        &self,
+        train: &Train,
+        from_to: &(Station, Station),
+        begin_at: u32,
+        reservations: &[Reservation],
+    ) -> Option<(RoutePath, Vec<u32>)> {
+        let (from, to) = from_to;
 
-    fn reachable_stations(&self, station: &Station) -> Vec<(Station, u32)> {
-        let outward_routes = self.routes_from(station);
+        if from == to {
+            let route_path = RoutePath {
+                from_to: from_to.clone(),
+                routes: vec![Route::identity(from)],
+            };
 
-        let available_stations = outward_routes
+            return Some((route_path, vec![0]));
+        }
+
+        let (arrivals, _) = dijkstra(
+            &(from.clone(), begin_at),
+            |(station, ready_at)| {
+                self.train_routes_from(train, station)
+                    .into_iter()
+                    .map(|route| {
+                        let arrive_at = route
+                            .earliest_departure(*ready_at, reservations)
+                            .saturating_add(route.travel_time);
+
+                        ((route.to().clone(), arrive_at), arrive_at - ready_at)
+                    })
+                    .collect_vec()
+            },
+            |(station, _)| station == to,
+        )?;
+
+        let (routes, held) = arrivals
             .iter()
-            .map(|route| route.to())
-            .cloned()
-            .collect_vec();
+            .tuple_windows()
+            .map(|((from, ready_at), (to, arrive_at))| {
+                self.train_routes_from(train, from)
+                    .into_iter()
+                    .filter(|route| route.is_to(to))
+                    .map(|route| (route, route.earliest_departure(*ready_at, reservations)))
+                    .find(|(route, departure)| {
+                        departure.saturating_add(route.travel_time) == *arrive_at
+                    })
+                    .map(|(route, departure)| (route.clone(), departure - ready_at))
+                    .unwrap()
+            })
+            .unzip();
 
-        let travel_time = outward_routes.into_iter().map(|route| route.travel_time);
+        Some((
+            RoutePath {
+                from_to: from_to.clone(),
+                routes,
+            },
+            held,
+        ))
+    }
 
-        zip(available_stations, travel_time).collect_vec()
+    fn train_routes_from(&self, train: &Train, station: &Station) -> Vec<&Route> {
+        routes_from(&self.routes, station)
+            .into_iter()
+            .filter(|route| train.can_use(route))
+            .collect_vec()
     }
 }
@@ -1,100 +1,530 @@
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::rc::Rc;
+use std::sync::Arc;
 
-use itertools::Either;
+use fixedbitset::FixedBitSet;
 
-use super::route_path::RouteMap;
+use super::bound::LowerBounds;
+use super::component::Component;
+use super::cost::CostModel;
+use super::feasibility::{InfeasibleError, Reason, UndeliverablePackage};
+use super::route_path::{Distances, Reservation, RouteMap};
 use super::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Action {
-    Pick(Package, Station),
-    Drop(Package, Station),
+    /// Pick the package where it waits, its start station or the hub it was left at
+    Pick(PackageId, StationId),
+    Drop(PackageId),
+    /// Leave the package at a hub for another train to carry on
+    Transfer(PackageId, StationId),
+    /// Bring the train to its end station, after which it takes no other action
+    Return(TrainId),
 }
 
 impl Action {
-    pub(super) fn package(&self) -> Package {
+    pub(super) fn package_id(&self) -> Option<PackageId> {
         match self {
-            Action::Pick(p, _) => p.clone(),
-            Action::Drop(p, _) => p.clone(),
+            Action::Pick(id, _) | Action::Drop(id) | Action::Transfer(id, _) => Some(*id),
+            Action::Return(_) => None,
         }
     }
 
-    pub(super) fn station(&self) -> Station {
+    fn station<'a>(&self, network: &'a super::Network) -> &'a Station {
         match self {
-            Action::Pick(_, s) => s.clone(),
-            Action::Drop(_, s) => s.clone(),
+            Action::Pick(_, station) | Action::Transfer(_, station) => &network.stations[*station],
+            Action::Drop(id) => network.packages[*id].to(),
+            Action::Return(id) => network.trains[*id].end_station.as_ref().unwrap(),
         }
     }
+
+    fn handling_time(&self, network: &super::Network) -> u32 {
+        self.package_id()
+            .map(|id| self.station(network).handling_time(&network.packages[id]))
+            .unwrap_or(0)
+    }
 }
 
-#[derive(Clone, Eq)]
+/// A package left at a hub, waiting for a train to pick it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Handoff {
+    station: StationId,
+    at: u32,
+}
+
+#[derive(Clone)]
 pub struct Network<'a> {
     pub train_states: Vec<Train<'a>>,
-    required_actions: Vec<Action>,
+    /// Packages picked by any train, by package id
+    picked: FixedBitSet,
+    /// Packages dropped at their destination, by package id
+    delivered: FixedBitSet,
+    /// Packages waiting at a hub, by package id
+    handoffs: Vec<Option<Handoff>>,
+    network: &'a super::Network,
+    /// Distances of every package through the routes of the trains able to carry it
+    package_distances: Arc<Vec<Arc<Distances>>>,
+    cost_model: Arc<dyn CostModel>,
+    lower_bounds: LowerBounds,
 }
 
 impl<'a> Network<'a> {
-    pub(super) fn new(network: &'a super::Network) -> Self {
-        let route_map = Rc::new(network.route_map());
+    #[cfg(test)]
+    pub(super) fn new(network: &'a super::Network, route_map: Arc<RouteMap>) -> Self {
+        Self::with_cost_model(network, route_map, Arc::new(super::cost::Makespan))
+    }
 
-        Self {
-            train_states: network
-                .trains
-                .iter()
-                .map(|train| Train {
-                    train,
-                    taken_actions: vec![],
-                    route_map: route_map.clone(),
+    pub(super) fn with_cost_model(
+        network: &'a super::Network,
+        route_map: Arc<RouteMap>,
+        cost_model: Arc<dyn CostModel>,
+    ) -> Self {
+        let workload = network.workload_lower_bound(&route_map);
+        let distances = Arc::new(network.distances(&route_map));
+        let package_distances = Arc::new(network.package_distances(&distances));
+
+        let train_route_maps = network.train_route_maps(&route_map);
+
+        let mut state = Self {
+            train_states: zip(&network.trains, train_route_maps)
+                .map(|(train, (route_map, distances))| {
+                    Train::new(train, network, route_map, distances)
                 })
                 .collect_vec(),
-            required_actions: network.actions(),
+            picked: FixedBitSet::with_capacity(network.packages.len()),
+            delivered: FixedBitSet::with_capacity(network.packages.len()),
+            handoffs: vec![None; network.packages.len()],
+            network,
+            package_distances,
+            cost_model,
+            lower_bounds: LowerBounds::default(),
+        };
+
+        state.lower_bounds = LowerBounds {
+            longest_delivery: state.travel_time_lower_bound(),
+            workload,
+            optimal: None,
+        };
+
+        state
+    }
+
+    /// Leave out every package and train outside `component`: its packages count as
+    /// delivered and its trains take no action.
+    pub(super) fn restricted_to(&self, component: &Component) -> Self {
+        let mut state = self.clone();
+
+        for package in &self.network.packages {
+            if !component.packages.contains(&package.id) {
+                state.picked.insert(package.id);
+                state.delivered.insert(package.id);
+            }
         }
+
+        for train in &mut state.train_states {
+            train.is_parked = !component.trains.contains(&train.train.id);
+        }
+
+        state
+    }
+
+    /// Combine the states of disjoint components, taking every train from its own component.
+    pub(super) fn merge(states: Vec<Self>) -> Self {
+        let mut states = states.into_iter();
+        let mut merged = states.next().unwrap();
+
+        for state in states {
+            merged.picked.intersect_with(&state.picked);
+            merged.delivered.intersect_with(&state.delivered);
+
+            for (merged_train, train) in zip(&mut merged.train_states, state.train_states) {
+                if !train.is_parked {
+                    *merged_train = train;
+                }
+            }
+        }
+
+        merged
+    }
+
+    /// Mark `travel_time_used` as optimal, e.g. when this state is the result of an exact search.
+    pub(super) fn proven_optimal(mut self) -> Self {
+        self.lower_bounds.optimal = Some(self.travel_time_used());
+        self
+    }
+
+    pub fn lower_bounds(&self) -> LowerBounds {
+        self.lower_bounds
+    }
+
+    /// How far `travel_time_used` can be from the optimum, as a fraction of `travel_time_used`.
+    pub fn gap(&self) -> f64 {
+        let travel_time_used = self.travel_time_used();
+
+        if travel_time_used == 0 {
+            return 0.0;
+        }
+
+        f64::from(travel_time_used.saturating_sub(self.lower_bounds.best()))
+            / f64::from(travel_time_used)
+    }
+
+    pub(super) fn cost(&self) -> u64 {
+        self.cost_model.cost(self)
     }
 
     pub(super) fn is_success(&self) -> bool {
-        self.available_actions().is_empty()
+        self.untaken_actions().is_empty()
     }
 
-    pub(super) fn take_available_actions(&self) -> Vec<(Network<'a>, u32)> {
+    pub(super) fn take_available_actions(&self) -> Vec<(Network<'a>, u64)> {
         let untaken_actions = self.untaken_actions();
-        let travel_time_used = self.travel_time_used();
+        let cost = self.cost();
 
-        self.clone()
-            .train_states
-            .iter_mut()
+        self.train_states
+            .iter()
             .enumerate()
             .flat_map(|(index, train_state)| {
                 train_state
                     .available_actions(&untaken_actions)
-                    .iter()
-                    .map(|action| {
-                        let mut new_train_states = self.train_states.clone();
+                    .into_iter()
+                    .filter(move |action| self.is_in_symmetry_order(index, action))
+                    .filter_map(move |action| {
+                        let mut new_state = self.clone();
+
+                        new_state.take_action(index, action).then_some(new_state)
+                    })
+            })
+            .filter(|new_state| new_state.can_deliver_on_time())
+            .map(|new_state| {
+                let new_cost = new_state.cost() - cost;
 
-                        let mut new_train_state = train_state.clone();
+                (new_state, new_cost)
+            })
+            .collect_vec()
+    }
 
-                        new_train_state.take_action(action);
+    /// Interchangeable packages are picked in id order, and interchangeable trains take their
+    /// first action in id order. Any itinerary can be renamed to follow that order, so only
+    /// symmetric branches are pruned.
+    fn is_in_symmetry_order(&self, index: usize, action: &Action) -> bool {
+        let is_package_in_order = match action {
+            Action::Pick(id, _) => self.network.packages[*id]
+                .twin
+                .is_none_or(|twin| self.picked.contains(twin)),
+            Action::Drop(_) | Action::Transfer(_, _) | Action::Return(_) => true,
+        };
+
+        let train = &self.train_states[index];
+        let is_train_in_order = !train.taken_actions.is_empty()
+            || train
+                .train
+                .twin
+                .is_none_or(|twin| !self.train_states[twin].taken_actions.is_empty());
+
+        is_package_in_order && is_train_in_order
+    }
 
-                        new_train_states[index] = new_train_state;
+    /// Let every train take its actions of `plan` in order, always moving the train with the
+    /// earliest clock first. A train waits to pick a package until it is left where the train
+    /// picks it. Return whether every action of the plan is taken.
+    pub(super) fn take_plan(&mut self, plan: &[Vec<Action>]) -> bool {
+        let mut next_indices = vec![0; plan.len()];
+
+        loop {
+            let pending = (0..plan.len())
+                .filter(|index| next_indices[*index] < plan[*index].len())
+                .collect_vec();
+
+            if pending.is_empty() {
+                return true;
+            }
+
+            let Some(index) = pending
+                .into_iter()
+                .filter(|index| self.is_waiting(&plan[*index][next_indices[*index]]))
+                .min_by_key(|index| self.train_states[*index].clock)
+            else {
+                return false;
+            };
+
+            if !self.take_action(index, &plan[index][next_indices[index]]) {
+                return false;
+            }
+
+            next_indices[index] += 1;
+        }
+    }
 
-                        new_train_states
-                    })
-                    .map(|train_states| Network {
-                        train_states,
-                        ..self.clone()
-                    })
-                    .map(|new_state| {
-                        (
-                            new_state.clone(),
-                            new_state.travel_time_used() - travel_time_used,
-                        )
+    /// Whether the package of a pick waits at its station, at its start or left at a hub.
+    fn is_waiting(&self, action: &Action) -> bool {
+        match action {
+            Action::Pick(id, station) => match self.handoffs[*id] {
+                Some(handoff) => handoff.station == *station,
+                None => {
+                    !self.picked.contains(*id) && self.network.packages[*id].from().id == *station
+                }
+            },
+            Action::Drop(_) | Action::Transfer(_, _) | Action::Return(_) => true,
+        }
+    }
+
+    /// Let the train at `index` take `action`, scheduled around the routes
+    /// already occupied by every train. Return whether the action is taken.
+    fn take_action(&mut self, index: usize, action: &Action) -> bool {
+        let reservations = self.reservations();
+
+        let ready_at = match action {
+            Action::Pick(id, _) => self.pickup(&self.network.packages[*id]).1,
+            _ => 0,
+        };
+
+        let is_taken = self.train_states[index].take_action(action, ready_at, &reservations);
+
+        if is_taken {
+            match action {
+                Action::Pick(id, _) => {
+                    self.picked.insert(*id);
+                    self.handoffs[*id] = None;
+                }
+                Action::Drop(id) => self.delivered.insert(*id),
+                Action::Transfer(id, station) => {
+                    self.handoffs[*id] = Some(Handoff {
+                        station: *station,
+                        at: self.train_states[index].clock,
                     })
-                    .collect_vec()
-            })
+                }
+                Action::Return(_) => {}
+            }
+        }
+
+        is_taken
+    }
+
+    /// Station where `package` waits to be picked, and the time it can be picked from.
+    fn pickup<'b>(&'b self, package: &'b Package) -> (&'b Station, u32) {
+        match self.handoffs[package.id] {
+            Some(handoff) => (&self.network.stations[handoff.station], handoff.at),
+            None => (package.from(), package.release.unwrap_or(0)),
+        }
+    }
+
+    fn reservations(&self) -> Vec<Reservation> {
+        self.train_states
+            .iter()
+            .flat_map(|train| train.legs.iter().flat_map(Leg::reservations))
             .collect_vec()
     }
 
+    /// Whether every undelivered package can still reach its destination before its deadline.
+    fn can_deliver_on_time(&self) -> bool {
+        self.undelivered_packages().all(|package| {
+            self.earliest_delivery(package)
+                .map(|deliver_at| deliver_at <= package.deadline.unwrap_or(u32::MAX))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Admissible estimate of the remaining cost, used as the A* heuristic.
+    pub(super) fn heuristic(&self) -> u64 {
+        self.cost_model.heuristic(self)
+    }
+
+    /// Every undelivered package still has to travel from where it is to its destination,
+    /// and every train still has to return to its end station, so the final `travel_time_used`
+    /// can never be less than the latest of those arrivals.
+    pub(super) fn travel_time_lower_bound(&self) -> u32 {
+        self.undelivered_packages()
+            .filter_map(|package| self.earliest_delivery(package))
+            .chain(
+                self.train_states
+                    .iter()
+                    .filter_map(|train| train.earliest_return()),
+            )
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Some train has to run at least until it delivers each undelivered package,
+    /// so the final `total_travel_time_used` grows by at least the longest of those runs.
+    pub(super) fn total_travel_time_lower_bound(&self) -> u64 {
+        let extension = self.extension_lower_bound(|_, extended_time| extended_time.into());
+
+        self.total_travel_time_used() + extension
+    }
+
+    /// Every undelivered package is dropped no earlier than the closest train can deliver it,
+    /// so it adds at least that time, times its priority, to `weighted_completion_time`.
+    pub(super) fn weighted_completion_time_lower_bound(&self) -> u64 {
+        let undelivered: u64 = self
+            .undelivered_packages()
+            .filter_map(|package| {
+                self.earliest_delivery(package)
+                    .map(|deliver_at| u64::from(package.priority) * u64::from(deliver_at))
+            })
+            .sum();
+
+        self.weighted_completion_time() + undelivered
+    }
+
+    /// Same as `total_travel_time_lower_bound`, priced by the train making each run.
+    pub(super) fn total_cost_lower_bound(&self) -> u64 {
+        let extension = self.extension_lower_bound(|train, extended_time| {
+            train.cost_of(train.travel_time_used().saturating_add(extended_time)) - train.cost()
+        });
+
+        self.total_cost() + extension
+    }
+
+    /// The largest, over undelivered packages, of the cheapest `extension_cost` of
+    /// running a train on until it can deliver the package.
+    ///
+    /// With hubs, the run can be split between trains handing the package off. Together they
+    /// still cover the rest of its path, at no less than the cost of the cheapest of them.
+    fn extension_lower_bound(&self, extension_cost: impl Fn(&Train, u32) -> u64) -> u64 {
+        let has_hubs = self.network.hubs().next().is_some();
+
+        self.undelivered_packages()
+            .filter_map(|package| match has_hubs {
+                false => {
+                    let pickup = self.pickup(package);
+
+                    self.delivering_trains(package)
+                        .filter_map(|train| {
+                            train
+                                .earliest_delivery(package, pickup)
+                                .map(|deliver_at| extension_cost(train, deliver_at - train.clock))
+                        })
+                        .min()
+                }
+                true => self.remaining_time(package).and_then(|remaining_time| {
+                    self.train_states
+                        .iter()
+                        .filter(|train| !train.is_done() && train.train.can_carry(package))
+                        .map(|train| extension_cost(train, remaining_time))
+                        .min()
+                }),
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Time left to carry `package` from where it is to its destination, whichever trains do it.
+    fn remaining_time(&self, package: &Package) -> Option<u32> {
+        let carrying_train = self
+            .train_states
+            .iter()
+            .find(|train| train.is_carrying(package));
+
+        let (station, handling_time) = match carrying_train {
+            Some(train) => (train.station, 0),
+            None => {
+                let (station, _) = self.pickup(package);
+
+                (station, station.handling_time(package))
+            }
+        };
+
+        self.package_distances[package.id]
+            .get(station, package.to())
+            .map(|travel_time| {
+                handling_time
+                    .saturating_add(travel_time)
+                    .saturating_add(package.to().handling_time(package))
+            })
+    }
+
+    /// Number of packages dropped at their destination, to tell how far a search got.
+    pub(super) fn delivered_count(&self) -> usize {
+        self.delivered.count_ones(..)
+    }
+
+    /// Packages left undelivered by the furthest state a search reached without finding an
+    /// itinerary. Each passed the checks made before the search, so it conflicts with the others.
+    pub(super) fn infeasible_error(&self) -> InfeasibleError {
+        InfeasibleError {
+            packages: self
+                .undelivered_packages()
+                .map(|package| UndeliverablePackage {
+                    package: package.clone(),
+                    reason: Reason::Conflicting,
+                })
+                .collect_vec(),
+        }
+    }
+
+    fn undelivered_packages(&self) -> impl Iterator<Item = &'a Package> + '_ {
+        self.network
+            .packages
+            .iter()
+            .filter(|package| !self.delivered.contains(package.id))
+    }
+
+    /// Time `package` can be dropped at its destination at the earliest.
+    ///
+    /// With hubs, trains can relay the package, each on its own routes, so no single train has
+    /// to reach the destination. It still travels through routes some train able to carry it
+    /// is allowed on, from where it is now.
+    fn earliest_delivery(&self, package: &Package) -> Option<u32> {
+        let pickup = self.pickup(package);
+
+        if self.network.hubs().next().is_none() {
+            return self
+                .delivering_trains(package)
+                .filter_map(|train| train.earliest_delivery(package, pickup))
+                .min();
+        }
+
+        let carrying_train = self
+            .train_states
+            .iter()
+            .find(|train| train.is_carrying(package));
+
+        let (station, leave_at) = match carrying_train {
+            Some(train) => (train.station, train.clock),
+            None => {
+                let (station, ready_at) = pickup;
+                let arrive_at = self
+                    .train_states
+                    .iter()
+                    .filter(|train| !train.is_done() && train.train.can_carry(package))
+                    .filter_map(|train| train.earliest_arrival(station))
+                    .min()?;
+
+                (
+                    station,
+                    arrive_at
+                        .max(ready_at)
+                        .saturating_add(station.handling_time(package)),
+                )
+            }
+        };
+
+        self.package_distances[package.id]
+            .get(station, package.to())
+            .map(|travel_time| {
+                leave_at
+                    .saturating_add(travel_time)
+                    .saturating_add(package.to().handling_time(package))
+            })
+    }
+
+    /// The train carrying `package`, or every train when nobody picked it yet.
+    fn delivering_trains<'b>(
+        &'b self,
+        package: &'b Package,
+    ) -> impl Iterator<Item = &'b Train<'a>> {
+        let carrying_train = self
+            .train_states
+            .iter()
+            .find(|train| train.is_carrying(package));
+
+        self.train_states.iter().filter(move |train| {
+            carrying_train.is_none_or(|carrying| carrying.train == train.train)
+        })
+    }
+
+    #[cfg(test)]
     fn available_actions(&self) -> Vec<Action> {
         let untaken_actions = self.untaken_actions();
 
@@ -106,6 +536,7 @@ impl<'a> Network<'a> {
             .collect_vec()
     }
 
+    #[cfg(test)]
     fn taken_actions(&self) -> Vec<Action> {
         self.train_states
             .iter()
@@ -114,12 +545,25 @@ impl<'a> Network<'a> {
     }
 
     fn untaken_actions(&self) -> Vec<Action> {
-        let taken_actions = self.taken_actions();
+        self.undelivered_packages()
+            .flat_map(|package| {
+                let pick = match self.handoffs[package.id] {
+                    Some(handoff) => Some(Action::Pick(package.id, handoff.station)),
+                    None => (!self.picked.contains(package.id))
+                        .then_some(Action::Pick(package.id, package.from().id)),
+                };
 
-        self.required_actions
-            .iter()
-            .filter(|action| !taken_actions.contains(action))
-            .cloned()
+                let transfers = self
+                    .network
+                    .hubs()
+                    .filter(|hub| *hub != package.to())
+                    .map(|hub| Action::Transfer(package.id, hub.id));
+
+                pick.into_iter()
+                    .chain([Action::Drop(package.id)])
+                    .chain(transfers)
+            })
+            .chain(self.train_states.iter().filter_map(Train::untaken_return))
             .collect_vec()
     }
 
@@ -133,14 +577,167 @@ impl<'a> Network<'a> {
     pub fn travel_time_used(&self) -> u32 {
         self.train_states
             .iter()
-            .map(|state| state.travel_time_used())
+            .map(|state| state.finish_time())
             .max()
-            .unwrap()
+            .unwrap_or(0)
     }
 
-    pub fn print_output(&self) {
-        self.print_instructions();
-        println!("Total time used: {}", self.travel_time_used())
+    pub fn total_travel_time_used(&self) -> u64 {
+        self.train_states
+            .iter()
+            .map(|state| u64::from(state.travel_time_used()))
+            .sum()
+    }
+
+    pub fn total_cost(&self) -> u64 {
+        self.train_states.iter().map(|state| state.cost()).sum()
+    }
+
+    /// Time every package is dropped at its destination, by package id, or `None` until it is.
+    pub fn delivery_times(&self) -> Vec<Option<u32>> {
+        let mut delivery_times = vec![None; self.network.packages.len()];
+
+        for (id, deliver_at) in self.train_states.iter().flat_map(Train::deliveries) {
+            delivery_times[id] = Some(deliver_at);
+        }
+
+        delivery_times
+    }
+
+    /// Sum of the time every delivered package is dropped at its destination, times its priority.
+    pub fn weighted_completion_time(&self) -> u64 {
+        self.train_states
+            .iter()
+            .flat_map(Train::deliveries)
+            .map(|(id, deliver_at)| {
+                u64::from(self.network.packages[id].priority) * u64::from(deliver_at)
+            })
+            .sum()
+    }
+
+    /// `weighted_completion_time`, plus the time every undelivered package has been on its way
+    /// so far, times its priority. Unlike `weighted_completion_time` alone, it grows whenever a
+    /// package is moved, even between hubs, and it equals it once every package is delivered.
+    pub(super) fn weighted_elapsed_time(&self) -> u64 {
+        let undelivered: u64 = self
+            .undelivered_packages()
+            .map(|package| {
+                let elapsed_time = match self.handoffs[package.id] {
+                    Some(handoff) => handoff.at,
+                    None => self
+                        .train_states
+                        .iter()
+                        .find(|train| train.is_carrying(package))
+                        .map_or(0, |train| train.clock),
+                };
+
+                u64::from(package.priority) * u64::from(elapsed_time)
+            })
+            .sum();
+
+        self.weighted_completion_time() + undelivered
+    }
+
+    pub fn output(&self) -> Output {
+        Output {
+            instructions: self
+                .instructions()
+                .iter()
+                .map(output::InstructionOutput::from)
+                .collect_vec(),
+            total_time_used: self.travel_time_used(),
+            total_train_time_used: self.total_travel_time_used(),
+            trains: self.train_outputs(),
+            packages: self.package_outputs(),
+            total_cost: self.total_cost(),
+            weighted_completion_time: self.weighted_completion_time(),
+            lower_bounds: self.lower_bounds(),
+            gap: self.gap(),
+        }
+    }
+
+    fn train_outputs(&self) -> Vec<output::TrainOutput> {
+        self.train_states
+            .iter()
+            .map(|state| output::TrainOutput {
+                train: state.train.name.clone(),
+                time_used: state.travel_time_used(),
+                cost: state.cost(),
+                utilization: state.utilization(),
+            })
+            .collect_vec()
+    }
+
+    fn package_outputs(&self) -> Vec<output::PackageOutput> {
+        zip(&self.network.packages, self.delivery_times())
+            .filter_map(|(package, deliver_at)| {
+                deliver_at.map(|deliver_at| output::PackageOutput {
+                    package: package.name.clone(),
+                    priority: package.priority,
+                    delivered_at: deliver_at,
+                })
+            })
+            .collect_vec()
+    }
+
+    /// Text output only has the sections the selected objective or the network make use of,
+    /// besides the instructions, delivery times and bounds. JSON output always has every section.
+    pub fn print_output(&self, format: args::OutputFormat, solver: &args::Solver) -> Result<()> {
+        match format {
+            args::OutputFormat::Text => {
+                let has_priorities = self
+                    .network
+                    .packages
+                    .iter()
+                    .any(|package| package.priority != 1);
+                let is_total = matches!(
+                    solver.objective,
+                    args::Objective::Total | args::Objective::MakespanThenTotal
+                );
+                let has_costs = self
+                    .network
+                    .trains
+                    .iter()
+                    .any(|train| train.cost_per_time > 0 || train.activation_cost > 0);
+                let is_cost = solver.objective == args::Objective::Cost || has_costs;
+
+                self.print_instructions();
+                println!("Total time used: {}", self.travel_time_used());
+
+                if is_total {
+                    println!("Total train time used: {}", self.total_travel_time_used());
+                }
+
+                if is_total || is_cost || !self.network.dimensions.is_empty() {
+                    self.train_outputs()
+                        .iter()
+                        .for_each(|train| println!("{train}"));
+                }
+
+                if is_cost {
+                    println!("Total cost: {}", self.total_cost());
+                }
+
+                self.package_outputs()
+                    .iter()
+                    .for_each(|package| println!("{package}"));
+
+                if solver.objective == args::Objective::WeightedCompletion || has_priorities {
+                    println!(
+                        "Weighted completion time: {}",
+                        self.weighted_completion_time()
+                    );
+                }
+
+                println!("Lower bound: {}", self.lower_bounds());
+                println!("Gap: {:.1}%", self.gap() * 100.0)
+            }
+            args::OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&self.output())?)
+            }
+        }
+
+        Ok(())
     }
 
     fn print_instructions(&self) {
@@ -158,46 +755,187 @@ impl<'a> Debug for Network<'a> {
     }
 }
 
+/// Everything the rest of the search depends on. States with the same key have the same cost
+/// and the same successors, whatever the order of the actions that led to them, so the search
+/// only expands one of them. The action history is only kept to output the itinerary.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    /// Cost so far, which can also depend on when past packages were delivered
+    cost: u64,
+    trains: Vec<TrainKey>,
+    delivered: FixedBitSet,
+    handoffs: Vec<Option<Handoff>>,
+    /// Reservations that can still hold a train, i.e. ending after the earliest train clock
+    reservations: Vec<Reservation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct TrainKey {
+    station: StationId,
+    clock: u32,
+    carrying: FixedBitSet,
+    is_idle: bool,
+    is_done: bool,
+}
+
+impl<'a> Network<'a> {
+    pub fn key(&self) -> Key {
+        let earliest_clock = self
+            .train_states
+            .iter()
+            .filter(|train| !train.is_done())
+            .map(|train| train.clock)
+            .min()
+            .unwrap_or(u32::MAX);
+
+        Key {
+            cost: self.cost(),
+            trains: self.train_states.iter().map(Train::key).collect_vec(),
+            delivered: self.delivered.clone(),
+            handoffs: self.handoffs.clone(),
+            reservations: self
+                .reservations()
+                .into_iter()
+                .filter(|reservation| reservation.end_at > earliest_clock)
+                .sorted_by_key(|reservation| (reservation.route.name.clone(), reservation.begin_at))
+                .collect_vec(),
+        }
+    }
+}
+
 impl<'a> PartialEq for Network<'a> {
     fn eq(&self, other: &Self) -> bool {
-        self.train_states == other.train_states
+        self.key() == other.key()
     }
 }
 
+impl<'a> Eq for Network<'a> {}
+
 impl<'a> Hash for Network<'a> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.train_states.hash(state);
+        self.key().hash(state);
     }
 }
 
-#[derive(Debug, Clone, Eq)]
+#[derive(Clone)]
 pub struct Train<'a> {
     pub train: &'a super::Train,
     pub taken_actions: Vec<Action>,
-    route_map: Rc<RouteMap>,
+    legs: Vec<Leg>,
+    /// Where the train is once done with its last taken action
+    station: &'a Station,
+    /// When the train is done with its last taken action
+    clock: u32,
+    /// Packages on board, by package id
+    carrying: FixedBitSet,
+    /// Total weight of the packages on board
+    load: u32,
+    /// Total size of the packages on board in every dimension of `Network::dimensions`
+    sizes: Vec<u32>,
+    network: &'a super::Network,
+    route_map: Arc<RouteMap>,
+    distances: Arc<Distances>,
+    /// Left out of the search, since it belongs to another component
+    is_parked: bool,
 }
 
-impl<'a> PartialEq for Train<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        self.train == other.train && self.taken_actions == other.taken_actions
+impl<'a> Debug for Train<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Train")
+            .field("train", &self.train.name)
+            .field("taken_actions", &self.taken_actions)
+            .field("legs", &self.legs)
+            .finish()
     }
 }
 
-impl<'a> Hash for Train<'a> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.train.hash(state);
-        self.taken_actions.hash(state);
+impl<'a> Train<'a> {
+    fn new(
+        train: &'a super::Train,
+        network: &'a super::Network,
+        route_map: Arc<RouteMap>,
+        distances: Arc<Distances>,
+    ) -> Self {
+        Self {
+            train,
+            taken_actions: vec![],
+            legs: vec![],
+            station: &train.initial_station,
+            clock: train.ready_time,
+            carrying: FixedBitSet::with_capacity(network.packages.len()),
+            load: 0,
+            sizes: vec![0; network.dimensions.len()],
+            network,
+            route_map,
+            distances,
+            is_parked: false,
+        }
     }
-}
 
-impl<'a> Train<'a> {
-    fn take_action(&mut self, action: &Action) {
-        if self.can_take(action) {
-            self.taken_actions.push(action.clone())
+    /// Take `action`, not picking before `ready_at`.
+    fn take_action(
+        &mut self,
+        action: &Action,
+        ready_at: u32,
+        reservations: &[Reservation],
+    ) -> bool {
+        if !self.can_take(action) {
+            return false;
+        }
+
+        let leg = self.leg(action, ready_at, reservations);
+
+        let is_on_time = match action {
+            Action::Pick(_, _) | Action::Transfer(_, _) | Action::Return(_) => true,
+            Action::Drop(id) => {
+                leg.end_at() <= self.network.packages[*id].deadline.unwrap_or(u32::MAX)
+            }
+        };
+
+        // Times saturate at `u32::MAX`, so a leg ending there would end later than it can tell
+        let is_within_shift = self
+            .train
+            .shift_end()
+            .is_none_or(|shift_end| leg.end_at() <= shift_end)
+            && leg.end_at() < u32::MAX;
+
+        if !is_on_time || !is_within_shift {
+            return false;
+        }
+
+        match action {
+            Action::Pick(id, _) => {
+                let package = &self.network.packages[*id];
+
+                self.carrying.insert(*id);
+                self.load += package.weight;
+
+                for (size, package_size) in zip(&mut self.sizes, &package.sizes) {
+                    *size = size.saturating_add(*package_size);
+                }
+            }
+            Action::Drop(id) | Action::Transfer(id, _) => {
+                let package = &self.network.packages[*id];
+
+                self.carrying.set(*id, false);
+                self.load -= package.weight;
+
+                for (size, package_size) in zip(&mut self.sizes, &package.sizes) {
+                    *size = size.saturating_sub(*package_size);
+                }
+            }
+            Action::Return(_) => {}
         }
+
+        self.station = action.station(self.network);
+        self.clock = leg.end_at();
+        self.taken_actions.push(*action);
+        self.legs.push(leg);
+
+        true
     }
 
-    fn available_actions<'b>(&'b self, actions: &'b [Action]) -> Vec<&Action> {
+    fn available_actions<'b>(&'b self, actions: &'b [Action]) -> Vec<&'b Action> {
         actions
             .iter()
             .filter(|action| self.can_take(action))
@@ -205,100 +943,263 @@ impl<'a> Train<'a> {
     }
 
     fn can_take(&self, action: &Action) -> bool {
+        if self.is_done() {
+            return false;
+        }
+
         match action {
-            Action::Pick(package, _) => self.can_pick(package),
-            Action::Drop(package, _) => self.can_drop(package),
+            Action::Pick(id, station) => self.can_pick(
+                &self.network.packages[*id],
+                &self.network.stations[*station],
+            ),
+            Action::Drop(id) => self.can_drop(&self.network.packages[*id]),
+            Action::Transfer(id, station) => self.can_transfer(
+                &self.network.packages[*id],
+                &self.network.stations[*station],
+            ),
+            Action::Return(id) => self.can_return(*id),
         }
     }
 
-    fn can_pick(&self, package: &Package) -> bool {
-        let is_route_exist = self
-            .route_map
-            .get(&(self.train.initial_station.clone(), package.from().clone()))
-            .is_some();
+    fn can_pick(&self, package: &Package, station: &Station) -> bool {
+        let is_reachable = self.earliest_arrival(station).is_some();
 
-        let is_enough_room = package.weight + self.current_weight() <= self.train.capacity;
+        let is_enough_room = self.train.can_fit(package, self.load, &self.sizes);
 
-        is_route_exist && is_enough_room
+        is_reachable && is_enough_room && self.train.is_compatible(package)
     }
 
     fn can_drop(&self, package: &Package) -> bool {
-        self.taken_actions.iter().any(|action| match action {
-            Action::Pick(_, _) => action.package() == package.clone(),
-            Action::Drop(_, _) => false,
-        })
+        let is_on_time = self
+            .earliest_arrival(package.to())
+            .map(|arrive_at| {
+                arrive_at.saturating_add(package.to().handling_time(package))
+                    <= package.deadline.unwrap_or(u32::MAX)
+            })
+            .unwrap_or(false);
+
+        is_on_time && self.is_carrying(package)
     }
 
-    fn current_weight(&self) -> u32 {
-        self.current_packages()
-            .iter()
-            .map(|package| package.weight)
-            .sum()
+    fn can_transfer(&self, package: &Package, station: &Station) -> bool {
+        self.is_carrying(package) && self.earliest_arrival(station).is_some()
     }
 
-    fn current_packages(&self) -> Vec<Package> {
-        let (picked_packages, dropped_packages): (Vec<_>, Vec<_>) =
-            self.taken_actions.iter().partition_map(|r| match r {
-                Action::Pick(p, _) => Either::Left(p),
-                Action::Drop(p, _) => Either::Right(p),
-            });
+    fn can_return(&self, train_id: TrainId) -> bool {
+        let is_reachable = self
+            .train
+            .end_station
+            .as_ref()
+            .and_then(|station| self.earliest_arrival(station))
+            .is_some();
 
-        picked_packages
-            .into_iter()
-            .filter(|package| !dropped_packages.contains(package))
-            .cloned()
-            .collect()
+        train_id == self.train.id && is_reachable && self.carrying.count_ones(..) == 0
     }
 
+    fn key(&self) -> TrainKey {
+        TrainKey {
+            station: self.station.id,
+            clock: self.clock,
+            carrying: self.carrying.clone(),
+            is_idle: self.is_idle(),
+            is_done: self.is_done(),
+        }
+    }
+
+    /// Whether the train takes no more action, back at its end station or left out of the search.
+    fn is_done(&self) -> bool {
+        self.is_parked || matches!(self.taken_actions.last(), Some(Action::Return(_)))
+    }
+
+    fn untaken_return(&self) -> Option<Action> {
+        match self.is_done() {
+            true => None,
+            false => self.train.return_action(),
+        }
+    }
+
+    fn is_carrying(&self, package: &Package) -> bool {
+        self.carrying.contains(package.id)
+    }
+
+    fn distance(&self, from: &Station, to: &Station) -> Option<u32> {
+        self.distances.get(from, to)
+    }
+
+    /// Time this train can drop `package` at the earliest, picking it first at `pickup`
+    /// when not carrying it.
+    fn earliest_delivery(&self, package: &Package, pickup: (&Station, u32)) -> Option<u32> {
+        let (pickup_station, ready_at) = pickup;
+
+        let arrive_at = if self.is_done() {
+            None
+        } else if self.is_carrying(package) {
+            self.earliest_arrival(package.to())
+        } else if self.train.can_carry(package) {
+            self.earliest_arrival(pickup_station)
+                .map(|arrive_at| {
+                    arrive_at
+                        .max(ready_at)
+                        .saturating_add(pickup_station.handling_time(package))
+                })
+                .zip(self.distance(pickup_station, package.to()))
+                .map(|(pick_at, travel_time)| pick_at.saturating_add(travel_time))
+        } else {
+            None
+        };
+
+        arrive_at.map(|arrive_at| arrive_at.saturating_add(package.to().handling_time(package)))
+    }
+
+    /// Time this train can be back at its end station, if it still has to return. A train
+    /// that never leaves its end station does not count, whenever its shift starts.
+    fn earliest_return(&self) -> Option<u32> {
+        let end_station = self.train.end_station.as_ref()?;
+
+        if self.is_done() || self.is_idle() && self.station == end_station {
+            return None;
+        }
+
+        self.earliest_arrival(end_station)
+    }
+
+    /// Time this train can arrive at `station` from its current position and clock,
+    /// or `None` when there is no route from where the train is now.
+    fn earliest_arrival(&self, station: &Station) -> Option<u32> {
+        self.distance(self.station, station)
+            .map(|travel_time| self.clock.saturating_add(travel_time))
+    }
+
+    #[cfg(test)]
+    fn current_weight(&self) -> u32 {
+        self.load
+    }
+
+    /// Time the train has been running since the start of its shift.
     fn travel_time_used(&self) -> u32 {
-        self.route_paths()
-            .iter()
-            .map(|state| state.travel_time())
-            .sum()
+        self.clock - self.train.ready_time
     }
 
-    fn route_paths(&self) -> Vec<RoutePath> {
-        if self.taken_actions.is_empty() {
-            return vec![];
+    /// When the train is done with its last taken action, or 0 when it never moves.
+    fn finish_time(&self) -> u32 {
+        match self.travel_time_used() == 0 && self.is_idle() {
+            true => 0,
+            false => self.clock,
         }
+    }
 
-        let froms = vec![
-            vec![self.train.initial_station.clone()],
-            self.taken_actions
-                .iter()
-                .take(self.taken_actions.len() - 1)
-                .map(|a| a.station())
-                .collect_vec(),
-        ]
-        .concat();
+    /// Packages this train dropped at their destination, with the time each one is dropped.
+    fn deliveries(&self) -> impl Iterator<Item = (PackageId, u32)> + '_ {
+        zip(&self.legs, &self.taken_actions).filter_map(|(leg, action)| match action {
+            Action::Drop(id) => Some((*id, leg.end_at())),
+            _ => None,
+        })
+    }
 
-        let tos = self
-            .taken_actions
-            .iter()
-            .take(self.taken_actions.len())
-            .map(|a| a.station());
+    /// Highest load on board at any time, in weight first, then in every dimension of
+    /// `Network::dimensions`.
+    fn peak_loads(&self) -> Vec<u32> {
+        let mut loads: Vec<u32> = vec![0; self.network.dimensions.len() + 1];
+        let mut peaks = loads.clone();
+
+        for action in &self.taken_actions {
+            let (package, is_on_board) = match action {
+                Action::Pick(id, _) => (&self.network.packages[*id], true),
+                Action::Drop(id) | Action::Transfer(id, _) => (&self.network.packages[*id], false),
+                Action::Return(_) => continue,
+            };
+
+            let package_loads = std::iter::once(&package.weight).chain(&package.sizes);
+
+            for ((load, peak), package_load) in zip(zip(&mut loads, &mut peaks), package_loads) {
+                match is_on_board {
+                    true => *load = load.saturating_add(*package_load),
+                    false => *load = load.saturating_sub(*package_load),
+                }
+
+                *peak = (*peak).max(*load);
+            }
+        }
+
+        peaks
+    }
 
-        let routes = zip(froms, tos);
+    fn utilization(&self) -> Vec<output::Utilization> {
+        let capacities = [Some(self.train.capacity)]
+            .into_iter()
+            .chain(self.train.capacities.iter().copied());
+        let dimensions = ["weight".to_string()]
+            .into_iter()
+            .chain(self.network.dimensions.iter().cloned());
 
-        routes
-            .map(|from_to| self.route_map.get(&from_to).unwrap().clone())
+        zip(dimensions, zip(self.peak_loads(), capacities))
+            .map(|(dimension, (peak_load, capacity))| output::Utilization {
+                dimension,
+                peak_load,
+                capacity,
+            })
             .collect_vec()
     }
 
-    fn instructions(&self) -> Vec<Instruction> {
-        let route_paths = self.route_paths();
-        let taken_actions = &self.taken_actions;
+    fn cost(&self) -> u64 {
+        self.cost_of(self.travel_time_used())
+    }
 
-        let mut begin_at = 0;
+    /// Cost of this train running until `travel_time_used`, or nothing when it never moves.
+    fn cost_of(&self, travel_time_used: u32) -> u64 {
+        if travel_time_used == 0 && self.is_idle() {
+            return 0;
+        }
 
-        zip(route_paths, taken_actions)
-            .flat_map(|(route_path, action)| {
-                let instructions = self.sub_instructions(&route_path, action, begin_at);
+        u64::from(self.train.activation_cost)
+            + u64::from(self.train.cost_per_time) * u64::from(travel_time_used)
+    }
 
-                begin_at += route_path.travel_time();
+    /// Whether the train has not handled any package.
+    fn is_idle(&self) -> bool {
+        self.taken_actions
+            .iter()
+            .all(|action| matches!(action, Action::Return(_)))
+    }
 
-                instructions
-            })
+    /// Travel from the current position toward `action`. Take the shortest route path
+    /// unless it is held by other trains, then take whatever path arrives first.
+    fn leg(&self, action: &Action, ready_at: u32, reservations: &[Reservation]) -> Leg {
+        let from_to = (self.station.clone(), action.station(self.network).clone());
+        let begin_at = self.clock;
+
+        let route_path = self.route_map.get(&from_to).unwrap().clone();
+        let held = route_path.held(begin_at, reservations);
+
+        let (route_path, held) = if held.iter().all(|held| *held == 0) {
+            (route_path, held)
+        } else {
+            let (route_path, held) = self
+                .network
+                .earliest_route_path(self.train, &from_to, begin_at, reservations)
+                .unwrap();
+
+            (Arc::new(route_path), held)
+        };
+
+        let mut leg = Leg {
+            route_path,
+            held,
+            begin_at,
+            waited: 0,
+            handled: action.handling_time(self.network),
+        };
+
+        if let Action::Pick(_, _) = action {
+            leg.waited = ready_at.saturating_sub(leg.arrive_at());
+        }
+
+        leg
+    }
+
+    fn instructions(&self) -> Vec<Instruction> {
+        zip(&self.legs, &self.taken_actions)
+            .flat_map(|(leg, action)| self.sub_instructions(leg, action))
             .fold(vec![], |mut acc, next| match acc.pop() {
                 Some(last) => {
                     acc.extend(last.combine(next));
@@ -311,50 +1212,59 @@ impl<'a> Train<'a> {
             })
     }
 
-    fn sub_instructions(
-        &self,
-        route_path: &RoutePath,
-        action: &Action,
-        begin_at: u32,
-    ) -> Vec<Instruction> {
-        let route_len = route_path.routes.len();
+    fn sub_instructions(&self, leg: &Leg, action: &Action) -> Vec<Instruction> {
+        let (from, to) = &leg.route_path.from_to;
+
+        if matches!(action, Action::Return(_)) && from == to {
+            return vec![];
+        }
+
+        let route_len = leg.route_path.routes.len();
 
         let is_last = |index: usize| route_len - 1 == index;
 
-        let mut begin_at = begin_at;
+        let mut begin_at = leg.begin_at;
 
-        let mut instructions = route_path
-            .routes
-            .iter()
+        let mut instructions = zip(&leg.route_path.routes, &leg.held)
             .enumerate()
-            .map(|(index, route)| {
+            .map(|(index, (route, held))| {
                 let mut builder = InstructionBuilder::default();
 
+                begin_at = begin_at.saturating_add(*held);
+
                 let _ = &builder
                     .begin_at(begin_at)
                     .train(self.train.clone())
-                    .route(route.clone());
+                    .route(route.clone())
+                    .waited(*held);
 
                 let _ = match (is_last(index), action) {
-                    (true, Action::Drop(p, _)) => builder.dropped_package(vec![p.clone()]),
+                    (true, Action::Drop(id)) => {
+                        builder.dropped_package(vec![self.network.packages[*id].clone()])
+                    }
+                    (true, Action::Transfer(id, _)) => {
+                        builder.transferred_package(vec![self.network.packages[*id].clone()])
+                    }
                     _ => &builder,
                 };
 
                 let instruction = builder.build().unwrap();
 
-                begin_at += route.travel_time;
+                begin_at = begin_at.saturating_add(route.travel_time);
 
                 instruction
             })
             .collect_vec();
 
-        if let Action::Pick(package, station) = action {
+        if let Action::Pick(id, station) = action {
             instructions.push(Instruction {
-                begin_at,
+                begin_at: begin_at.saturating_add(leg.waited),
                 train: self.train.clone(),
-                route: Route::identity(station),
-                picked_package: vec![package.clone()],
+                route: Route::identity(&self.network.stations[*station]),
+                picked_package: vec![self.network.packages[*id].clone()],
                 dropped_package: vec![],
+                transferred_package: vec![],
+                waited: leg.waited,
             })
         }
 
@@ -362,32 +1272,77 @@ impl<'a> Train<'a> {
     }
 }
 
+/// Travel of a train toward one of its taken actions, including the time it is held
+/// before each route, the time it waits at the station before taking the action
+/// and the time to pick or drop the package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Leg {
+    route_path: Arc<RoutePath>,
+    held: Vec<u32>,
+    begin_at: u32,
+    waited: u32,
+    handled: u32,
+}
+
+impl Leg {
+    fn arrive_at(&self) -> u32 {
+        self.held
+            .iter()
+            .fold(self.begin_at, |arrive_at, held| {
+                arrive_at.saturating_add(*held)
+            })
+            .saturating_add(self.route_path.travel_time())
+    }
+
+    fn end_at(&self) -> u32 {
+        self.arrive_at()
+            .saturating_add(self.waited)
+            .saturating_add(self.handled)
+    }
+
+    fn reservations(&self) -> Vec<Reservation> {
+        let mut begin_at = self.begin_at;
+
+        zip(&self.route_path.routes, &self.held)
+            .filter_map(|(route, held)| {
+                begin_at = begin_at.saturating_add(*held);
+
+                let reservation = Reservation {
+                    route: route.clone(),
+                    begin_at,
+                    end_at: begin_at.saturating_add(route.travel_time),
+                };
+
+                begin_at = reservation.end_at;
+
+                route.capacity.map(|_| reservation)
+            })
+            .collect_vec()
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
 
+    use crate::args;
     use crate::model::case;
 
     #[test]
     fn train_take_action_diverge() {
         let network = case::diverge();
 
-        let mut state = Network::new(&network);
+        let mut state = Network::new(&network, Arc::new(network.route_map()));
 
-        let possible_actions = &state.required_actions;
+        let possible_actions = network.actions();
 
         let (pick_p1, drop_p1, pick_p2, drop_p2) = possible_actions.iter().collect_tuple().unwrap();
 
         assert_state_eq(
             &state,
             vec![],
-            vec![
-                pick_p1.clone(),
-                drop_p1.clone(),
-                pick_p2.clone(),
-                drop_p2.clone(),
-            ],
-            vec![pick_p1.clone(), pick_p2.clone()],
+            vec![*pick_p1, *drop_p1, *pick_p2, *drop_p2],
+            vec![*pick_p1, *pick_p2],
             0,
             0,
             false,
@@ -396,13 +1351,13 @@ pub mod test {
             0,
         );
 
-        state.train_states[0].take_action(pick_p1);
+        state.take_action(0, pick_p1);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone()],
-            vec![drop_p1.clone(), pick_p2.clone(), drop_p2.clone()],
-            vec![drop_p1.clone(), pick_p2.clone()],
+            vec![*pick_p1],
+            vec![*drop_p1, *pick_p2, *drop_p2],
+            vec![*drop_p1, *pick_p2],
             50,
             2,
             false,
@@ -411,13 +1366,13 @@ pub mod test {
             2,
         );
 
-        state.train_states[0].take_action(drop_p1);
+        state.take_action(0, drop_p1);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone(), drop_p1.clone()],
-            vec![pick_p2.clone(), drop_p2.clone()],
-            vec![pick_p2.clone()],
+            vec![*pick_p1, *drop_p1],
+            vec![*pick_p2, *drop_p2],
+            vec![*pick_p2],
             60,
             2,
             false,
@@ -426,13 +1381,13 @@ pub mod test {
             2,
         );
 
-        state.train_states[0].take_action(pick_p2);
+        state.take_action(0, pick_p2);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone(), drop_p1.clone(), pick_p2.clone()],
-            vec![drop_p2.clone()],
-            vec![drop_p2.clone()],
+            vec![*pick_p1, *drop_p1, *pick_p2],
+            vec![*drop_p2],
+            vec![*drop_p2],
             160,
             6,
             false,
@@ -441,16 +1396,11 @@ pub mod test {
             6,
         );
 
-        state.train_states[0].take_action(drop_p2);
+        state.take_action(0, drop_p2);
 
         assert_state_eq(
             &state,
-            vec![
-                pick_p1.clone(),
-                drop_p1.clone(),
-                pick_p2.clone(),
-                drop_p2.clone(),
-            ],
+            vec![*pick_p1, *drop_p1, *pick_p2, *drop_p2],
             vec![],
             vec![],
             170,
@@ -466,21 +1416,16 @@ pub mod test {
     fn train_take_action_multiple_packages_small_train() {
         let network = case::multiple_packages_small_train();
 
-        let mut state = Network::new(&network);
-        let possible_actions = &state.required_actions;
+        let mut state = Network::new(&network, Arc::new(network.route_map()));
+        let possible_actions = network.actions();
 
         let (pick_p1, drop_p1, pick_p2, drop_p2) = possible_actions.iter().collect_tuple().unwrap();
 
         assert_state_eq(
             &state,
             vec![],
-            vec![
-                pick_p1.clone(),
-                drop_p1.clone(),
-                pick_p2.clone(),
-                drop_p2.clone(),
-            ],
-            vec![pick_p1.clone(), pick_p2.clone()],
+            vec![*pick_p1, *drop_p1, *pick_p2, *drop_p2],
+            vec![*pick_p1, *pick_p2],
             0,
             0,
             false,
@@ -489,13 +1434,13 @@ pub mod test {
             0,
         );
 
-        state.train_states[0].take_action(pick_p1);
+        state.take_action(0, pick_p1);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone()],
-            vec![drop_p1.clone(), pick_p2.clone(), drop_p2.clone()],
-            vec![drop_p1.clone()],
+            vec![*pick_p1],
+            vec![*drop_p1, *pick_p2, *drop_p2],
+            vec![*drop_p1],
             0,
             1,
             false,
@@ -504,13 +1449,13 @@ pub mod test {
             1,
         );
 
-        state.train_states[0].take_action(drop_p1);
+        state.take_action(0, drop_p1);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone(), drop_p1.clone()],
-            vec![pick_p2.clone(), drop_p2.clone()],
-            vec![pick_p2.clone()],
+            vec![*pick_p1, *drop_p1],
+            vec![*pick_p2, *drop_p2],
+            vec![*pick_p2],
             10,
             1,
             false,
@@ -519,13 +1464,13 @@ pub mod test {
             1,
         );
 
-        state.train_states[0].take_action(pick_p2);
+        state.take_action(0, pick_p2);
 
         assert_state_eq(
             &state,
-            vec![pick_p1.clone(), drop_p1.clone(), pick_p2.clone()],
-            vec![drop_p2.clone()],
-            vec![drop_p2.clone()],
+            vec![*pick_p1, *drop_p1, *pick_p2],
+            vec![*drop_p2],
+            vec![*drop_p2],
             20,
             3,
             false,
@@ -534,16 +1479,11 @@ pub mod test {
             3,
         );
 
-        state.train_states[0].take_action(drop_p2);
+        state.take_action(0, drop_p2);
 
         assert_state_eq(
             &state,
-            vec![
-                pick_p1.clone(),
-                drop_p1.clone(),
-                pick_p2.clone(),
-                drop_p2.clone(),
-            ],
+            vec![*pick_p1, *drop_p1, *pick_p2, *drop_p2],
             vec![],
             vec![],
             30,
@@ -555,11 +1495,254 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn train_available_actions_one_way() {
+        let network = case::one_way();
+
+        let mut state = Network::new(&network, Arc::new(network.route_map()));
+
+        let (pick_p1, drop_p1, pick_p2, drop_p2) =
+            network.actions().into_iter().collect_tuple().unwrap();
+
+        assert_eq!(state.available_actions(), vec![pick_p1, pick_p2]);
+
+        state.take_action(0, &pick_p1);
+
+        assert_eq!(state.available_actions(), vec![drop_p1]);
+
+        state.take_action(0, &drop_p1);
+
+        assert_eq!(state.available_actions(), vec![]);
+        assert!(!state.train_states[0].can_take(&pick_p2));
+        assert!(!state.train_states[0].can_take(&drop_p2));
+        assert!(!state.train_states[0].can_drop(&network.packages[drop_p2.package_id().unwrap()]));
+    }
+
+    #[test]
+    fn relay_lower_bounds() {
+        let mut input = args::case::relay();
+        input.routes.push(args::Route {
+            name: "AC".into(),
+            from_to: ("A".into(), "C".into()),
+            travel_time: 100,
+            ..Default::default()
+        });
+        let network = super::super::Network::try_from(input).unwrap();
+
+        let state = network.optimal_itinerary().unwrap();
+
+        assert_eq!(state.travel_time_used(), 20);
+        assert_eq!(state.lower_bounds().longest_delivery, 20);
+    }
+
+    #[test]
+    fn network_output_direct() {
+        let network = case::direct();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(output.total_time_used, 20);
+        assert_eq!(output.total_train_time_used, 20);
+        assert_eq!(
+            output.lower_bounds,
+            LowerBounds {
+                longest_delivery: 20,
+                workload: 20,
+                optimal: Some(20)
+            }
+        );
+        assert_eq!(output.gap, 0.0);
+        assert_eq!(output.instructions.len(), 2);
+
+        let instruction = &output.instructions[0];
+
+        assert_eq!(instruction.begin_at, 0);
+        assert_eq!(instruction.train, "T");
+        assert_eq!(
+            (instruction.from.as_str(), instruction.to.as_str()),
+            ("A", "B")
+        );
+        assert_eq!(instruction.route, "AB");
+        assert_eq!(instruction.picked_packages, vec!["P".to_string()]);
+        assert!(instruction.dropped_packages.is_empty());
+
+        let instruction = &output.instructions[1];
+
+        assert_eq!(instruction.begin_at, 10);
+        assert_eq!(instruction.dropped_packages, vec!["P".to_string()]);
+    }
+
+    #[test]
+    fn network_output_depot() {
+        let network = case::depot();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(output.total_time_used, 40);
+        assert_eq!(
+            output
+                .instructions
+                .iter()
+                .map(|instruction| (
+                    instruction.begin_at,
+                    instruction.from.as_str(),
+                    instruction.to.as_str()
+                ))
+                .collect_vec(),
+            vec![
+                (0, "A", "B"),
+                (10, "B", "C"),
+                (20, "C", "B"),
+                (30, "B", "A")
+            ]
+        );
+        assert_eq!(
+            output.instructions[1].dropped_packages,
+            vec!["P".to_string()]
+        );
+    }
+
+    #[test]
+    fn network_output_release() {
+        let network = case::release();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(output.total_time_used, 50);
+        assert_eq!(output.instructions.len(), 2);
+        assert_eq!(output.instructions[0].begin_at, 30);
+        assert_eq!(output.instructions[0].waited, 30);
+        assert_eq!(
+            output.instructions[0].picked_packages,
+            vec!["P".to_string()]
+        );
+        assert_eq!(output.instructions[1].begin_at, 40);
+        assert_eq!(output.instructions[1].waited, 0);
+    }
+
+    #[test]
+    fn network_output_handling() {
+        let network = case::handling();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert_eq!(output.total_time_used, 35);
+        assert_eq!(output.instructions.len(), 2);
+        assert_eq!(output.instructions[0].begin_at, 5);
+        assert_eq!(output.instructions[0].waited, 0);
+        assert_eq!(output.instructions[1].begin_at, 15);
+    }
+
+    #[test]
+    fn network_output_single_track() {
+        let network = case::single_track();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        let held = output
+            .instructions
+            .iter()
+            .filter(|instruction| instruction.waited > 0)
+            .collect_vec();
+
+        assert_eq!(held.len(), 1);
+        assert_eq!(held[0].begin_at, 10);
+        assert_eq!(held[0].waited, 10);
+        assert_eq!(held[0].route, "AB");
+    }
+
+    #[test]
+    fn network_output_single_track_detour() {
+        let network = case::single_track_detour();
+
+        let output = network.optimal_itinerary().unwrap().output();
+
+        assert!(output
+            .instructions
+            .iter()
+            .all(|instruction| instruction.waited == 0));
+        assert_eq!(
+            output
+                .instructions
+                .iter()
+                .map(|instruction| instruction.route.as_str())
+                .sorted()
+                .collect_vec(),
+            vec!["AB", "AC", "BC"]
+        );
+    }
+
+    #[test]
+    fn network_take_available_actions_deadline() {
+        let network = case::deadline();
+
+        let state = Network::new(&network, Arc::new(network.route_map()));
+
+        // Picking P2 first can only drop P1 at 140, so only picking P1 is left.
+        let successor_states = state.take_available_actions();
+        assert_eq!(successor_states.len(), 1);
+
+        let ((state, _),) = successor_states.into_iter().collect_tuple().unwrap();
+        assert_eq!(
+            state.taken_actions(),
+            vec![network.packages[0].actions()[0]]
+        );
+    }
+
+    #[test]
+    fn network_eq_ignores_action_order() {
+        let network = case::multiple_packages_big_train();
+
+        let (pick_p1, drop_p1, pick_p2, _) = network.actions().into_iter().collect_tuple().unwrap();
+
+        let start = Network::new(&network, Arc::new(network.route_map()));
+
+        let mut state = start.clone();
+        state.take_action(0, &pick_p1);
+        state.take_action(0, &pick_p2);
+
+        let mut other = start.clone();
+        other.take_action(0, &pick_p2);
+        other.take_action(0, &pick_p1);
+
+        assert_ne!(state.taken_actions(), other.taken_actions());
+        assert_eq!(state, other);
+
+        state.take_action(0, &drop_p1);
+
+        assert_ne!(state, other);
+    }
+
+    #[test]
+    fn network_take_available_actions_twins() {
+        let mut input = crate::args::case::multiple_packages_small_train();
+        input.trains.push(crate::args::Train {
+            name: "T2".into(),
+            ..input.trains[0].clone()
+        });
+        let network = super::super::Network::try_from(input).unwrap();
+
+        let state = Network::new(&network, Arc::new(network.route_map()));
+
+        // Only the first train may pick, and only the first package.
+        let successor_states = state.take_available_actions();
+        assert_eq!(successor_states.len(), 1);
+
+        let ((state, _),) = successor_states.into_iter().collect_tuple().unwrap();
+        assert_eq!(
+            state.train_states[0].taken_actions,
+            vec![Action::Pick(0, 0)]
+        );
+
+        // The first train is full: it can only drop, or the second train picks the second package.
+        assert_eq!(state.take_available_actions().len(), 2);
+    }
+
     #[test]
     fn network_take_available_actions_diverge() {
         let network = case::diverge();
 
-        let state = Network::new(&network);
+        let state = Network::new(&network, Arc::new(network.route_map()));
 
         let successor_states = state.take_available_actions();
         assert_eq!(successor_states.len(), 2);
@@ -585,6 +1768,7 @@ pub mod test {
         assert_eq!(successor_states.len(), 0);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn assert_state_eq(
         state: &Network,
         taken_actions: Vec<Action>,
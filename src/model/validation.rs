@@ -0,0 +1,395 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub argument: String,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(argument: &impl Display, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            argument: argument.to_string(),
+            message,
+        }
+    }
+
+    fn warning(argument: &impl Display, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            argument: argument.to_string(),
+            message,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        write!(f, "{severity}: `{}`: {}", self.argument, self.message)
+    }
+}
+
+/// Collect every problem of the input network instead of stopping at the first one.
+///
+/// Feasibility of each package is only checked when there is no other error,
+/// since it needs a well-formed `Network`.
+pub fn validate(input: &args::Network) -> Vec<Diagnostic> {
+    let diagnostics = [
+        duplicate_names(&input.stations, |station| &station.name, "station"),
+        duplicate_names(&input.routes, |route| &route.name, "route"),
+        duplicate_names(&input.packages, |package| &package.name, "package"),
+        duplicate_names(&input.trains, |train| &train.name, "train"),
+        unknown_stations(input),
+        routes(input),
+        packages(input),
+        trains(input),
+    ]
+    .concat();
+
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        return diagnostics;
+    }
+
+    [
+        diagnostics,
+        undeliverable_packages(input),
+        unreachable_end_stations(input),
+    ]
+    .concat()
+}
+
+fn duplicate_names<T: Display>(
+    items: &[T],
+    name: impl Fn(&T) -> &String,
+    kind: &str,
+) -> Vec<Diagnostic> {
+    let mut names = HashSet::new();
+
+    items
+        .iter()
+        .filter(|item| !names.insert(name(item)))
+        .map(|item| Diagnostic::error(item, format!("duplicate {kind} name `{}`", name(item))))
+        .collect_vec()
+}
+
+fn unknown_stations(input: &args::Network) -> Vec<Diagnostic> {
+    let station_names: HashSet<_> = input.stations.iter().map(|station| &station.name).collect();
+
+    let referenced_station_names = input
+        .routes
+        .iter()
+        .flat_map(|route| {
+            let (from, to) = &route.from_to;
+
+            [(route.to_string(), from), (route.to_string(), to)]
+        })
+        .chain(input.packages.iter().flat_map(|package| {
+            let (from, to) = &package.from_to;
+
+            [(package.to_string(), from), (package.to_string(), to)]
+        }))
+        .chain(input.trains.iter().flat_map(|train| {
+            [
+                Some(&train.initial_station_name),
+                train.end_station_name.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(|station_name| (train.to_string(), station_name))
+        }));
+
+    referenced_station_names
+        .filter(|(_, station_name)| !station_names.contains(station_name))
+        .unique()
+        .map(|(argument, station_name)| {
+            Diagnostic::error(&argument, format!("station not found: {station_name}"))
+        })
+        .collect_vec()
+}
+
+fn routes(input: &args::Network) -> Vec<Diagnostic> {
+    input
+        .routes
+        .iter()
+        .flat_map(|route| {
+            let self_loop = (route.from_to.0 == route.from_to.1).then(|| {
+                Diagnostic::warning(
+                    route,
+                    "route starts and ends at the same station".to_string(),
+                )
+            });
+
+            let zero_capacity = (route.capacity == Some(0))
+                .then(|| Diagnostic::error(route, "route capacity must be at least 1".to_string()));
+
+            [self_loop, zero_capacity].into_iter().flatten()
+        })
+        .collect_vec()
+}
+
+fn packages(input: &args::Network) -> Vec<Diagnostic> {
+    let same_station = input
+        .packages
+        .iter()
+        .filter(|package| package.from_to.0 == package.from_to.1)
+        .map(|package| {
+            Diagnostic::warning(
+                package,
+                "package start and destination are the same station".to_string(),
+            )
+        });
+
+    let handling_overflow = input
+        .packages
+        .iter()
+        .cartesian_product(&input.stations)
+        .filter(|(package, station)| {
+            station
+                .handling_time_per_weight
+                .checked_mul(package.weight)
+                .and_then(|time| time.checked_add(station.handling_time))
+                .is_none()
+        })
+        .map(|(package, station)| {
+            Diagnostic::error(
+                package,
+                format!("handling time at station {} is too large", station.name),
+            )
+        });
+
+    let zero_priority = input
+        .packages
+        .iter()
+        .filter(|package| package.priority == Some(0))
+        .map(|package| {
+            Diagnostic::error(package, "package priority must be at least 1".to_string())
+        });
+
+    same_station
+        .chain(handling_overflow)
+        .chain(zero_priority)
+        .collect_vec()
+}
+
+fn trains(input: &args::Network) -> Vec<Diagnostic> {
+    input
+        .trains
+        .iter()
+        .filter(|train| train.capacity == 0)
+        .map(|train| {
+            Diagnostic::warning(
+                train,
+                "train has zero capacity and cannot carry any package".to_string(),
+            )
+        })
+        .collect_vec()
+}
+
+fn undeliverable_packages(input: &args::Network) -> Vec<Diagnostic> {
+    Network::try_from(input.clone())
+        .map(|network| {
+            network
+                .undeliverable_packages(&Arc::new(network.route_map()))
+                .into_iter()
+                .map(|undeliverable| {
+                    let package = input
+                        .packages
+                        .iter()
+                        .find(|package| package.name == undeliverable.package.name)
+                        .unwrap();
+
+                    Diagnostic::error(package, undeliverable.reason.to_string())
+                })
+                .collect_vec()
+        })
+        .unwrap_or_default()
+}
+
+fn unreachable_end_stations(input: &args::Network) -> Vec<Diagnostic> {
+    Network::try_from(input.clone())
+        .map(|network| {
+            let train_route_maps = network.train_route_maps(&Arc::new(network.route_map()));
+
+            zip(&input.trains, zip(&network.trains, train_route_maps))
+                .filter(|(_, (train, (route_map, _)))| {
+                    train.end_station.as_ref().is_some_and(|end_station| {
+                        !route_map
+                            .contains_key(&(train.initial_station.clone(), end_station.clone()))
+                    })
+                })
+                .map(|(train, _)| {
+                    Diagnostic::error(train, "end station is unreachable".to_string())
+                })
+                .collect_vec()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    use crate::args;
+
+    fn summary(input: &args::Network) -> Vec<(Severity, String)> {
+        validate(input)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.severity, diagnostic.argument))
+            .collect_vec()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(summary(&args::case::diverge()), vec![]);
+    }
+
+    #[test]
+    fn collect_every_error() {
+        let mut input = args::case::diverge();
+
+        input.stations.push(input.stations[0].clone());
+        input.routes[1].from_to.1 = "X".into();
+        input.packages[1].name = "P1".into();
+        input.packages[1].from_to.0 = "Y".into();
+        input.trains.push(input.trains[0].clone());
+
+        assert_eq!(
+            summary(&input),
+            vec![
+                (Severity::Error, "--station A".into()),
+                (Severity::Error, "--package P1,5,Y,E".into()),
+                (Severity::Error, "--train T,10,C".into()),
+                (Severity::Error, "--route BC,B,X,50".into()),
+                (Severity::Error, "--package P1,5,Y,E".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let mut input = args::case::direct();
+
+        input.routes[0].from_to.1 = "A".into();
+        input.packages[0].from_to.1 = "A".into();
+        input.trains.push(args::Train {
+            name: "T0".into(),
+            capacity: 0,
+            initial_station_name: "A".into(),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            summary(&input),
+            vec![
+                (Severity::Warning, "--route AB,A,A,10".into()),
+                (Severity::Warning, "--package P,5,A,A".into()),
+                (Severity::Warning, "--train T0,0,A".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn handling_time_overflow() {
+        let mut input = args::case::handling();
+        input.packages[0].weight = u32::MAX;
+
+        assert_eq!(
+            validate(&input),
+            vec![Diagnostic::error(
+                &input.packages[0],
+                "handling time at station C is too large".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn zero_priority() {
+        let mut input = args::case::priority();
+        input.packages[0].priority = Some(0);
+
+        assert_eq!(
+            summary(&input),
+            vec![(Severity::Error, "--package P1,5,A,B,priority=0".into())]
+        );
+    }
+
+    #[test]
+    fn zero_capacity_route() {
+        let mut input = args::case::single_track();
+        input.routes[0].capacity = Some(0);
+
+        assert_eq!(
+            summary(&input),
+            vec![(Severity::Error, "--route AB,A,B,10,capacity=0".into())]
+        );
+    }
+
+    #[test]
+    fn undeliverable_package() {
+        let mut input = args::case::islands();
+        input.packages[0].from_to.1 = "C".into();
+
+        assert_eq!(
+            validate(&input),
+            vec![Diagnostic::error(
+                &"--package P,5,A,C",
+                feasibility::Reason::UnreachableDestination.to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn undeliverable_package_one_way() {
+        let mut input = args::case::one_way();
+        input.packages[0].from_to = ("C".into(), "B".into());
+        input.packages[1].from_to = ("B".into(), "A".into());
+
+        assert_eq!(
+            validate(&input),
+            vec![
+                Diagnostic::error(
+                    &"--package P1,5,C,B",
+                    feasibility::Reason::UnreachableDestination.to_string()
+                ),
+                Diagnostic::error(
+                    &"--package P2,5,B,A",
+                    feasibility::Reason::UnreachableDestination.to_string()
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_end_station() {
+        let mut input = args::case::islands();
+        input.trains[0].end_station_name = Some("C".into());
+
+        assert_eq!(
+            summary(&input),
+            vec![(Severity::Error, "--train T,5,A,end=C".into())]
+        );
+    }
+}